@@ -2,7 +2,7 @@
 extern crate sublime_fuzzy;
 extern crate test;
 
-use sublime_fuzzy::{best_match, format_simple};
+use sublime_fuzzy::{best_match, format_simple, FuzzySearch};
 use test::Bencher;
 
 #[bench]
@@ -85,3 +85,19 @@ fn long_middle_close(b: &mut Bencher) {
         )
     });
 }
+
+#[bench]
+fn pattern_many_targets(b: &mut Bencher) {
+    let targets = (0..1000)
+        .map(|i| format!("src/module_{}/some_controller_{}.rs", i % 17, i))
+        .collect::<Vec<String>>();
+
+    b.iter(|| {
+        let mut pattern = FuzzySearch::with_query("mscr").compile();
+
+        targets
+            .iter()
+            .filter(|t| pattern.best_match(t).is_some())
+            .count()
+    })
+}
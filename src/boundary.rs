@@ -0,0 +1,177 @@
+/// Rules for finding the starts of words in target strings, see
+/// [`FuzzySearch::word_boundary`](crate::FuzzySearch::word_boundary).
+///
+/// Matching a word start adds [`Scoring::bonus_word_start`](crate::Scoring::bonus_word_start)
+/// to the score. The first char of a string and the first char after a separator always
+/// start a word.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use sublime_fuzzy::{FuzzySearch, WordBoundary};
+///
+/// let boundary = WordBoundary {
+///     separators: vec!['-'],
+///     ..WordBoundary::prose()
+/// };
+///
+/// let m = FuzzySearch::new("wk", "well-known")
+///     .word_boundary(boundary)
+///     .best_match()
+///     .unwrap();
+///
+/// assert_eq!(m.matched_indices().cloned().collect::<Vec<usize>>(), vec![0, 5]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WordBoundary {
+    /// All chars that are not alphanumeric separate words.
+    pub non_alphanumeric: bool,
+    /// Additional chars separating words.
+    pub separators: Vec<char>,
+    /// An uppercase char following a char that is not uppercase starts a word, e.g. `Cartoon`
+    /// in `SoccerCartoon`. In runs of uppercase chars, the last one starts a word if it is
+    /// followed by a lowercase char, e.g. `Parser` in `HTMLParser`.
+    pub case_change: bool,
+    /// A change from letters to digits (or the other way around) starts a word,
+    /// e.g. `8` and `to` in `utf8to16`.
+    pub letter_digit: bool,
+    /// Words are found with Unicode text segmentation ([UAX #29](https://www.unicode.org/reports/tr29/#Word_Boundaries)),
+    /// the first alphanumeric char of every word starts a word. This handles scripts without
+    /// case or separators, e.g. every ideograph in `東京` and `東` in `Tokyo東京` start words.
+    ///
+    /// Only has an effect with the `unicode-segmentation` feature.
+    pub unicode_words: bool,
+}
+
+impl WordBoundary {
+    /// Creates rules for identifiers in source code like `snake_case`, `camelCase`,
+    /// `kebab-case` and `utf8Decoder` (this is also the default).
+    pub fn identifiers() -> Self {
+        WordBoundary {
+            non_alphanumeric: true,
+            separators: Vec::new(),
+            case_change: true,
+            letter_digit: true,
+            unicode_words: false,
+        }
+    }
+
+    /// Creates rules for file paths. Path separators, `.`, `_`, `-` and spaces separate words,
+    /// and so do case changes in file names like `SoccerCartoonController.cs`.
+    pub fn paths() -> Self {
+        WordBoundary {
+            non_alphanumeric: false,
+            separators: vec!['/', '\\', '.', '_', '-', ' '],
+            case_change: true,
+            letter_digit: false,
+            unicode_words: false,
+        }
+    }
+
+    /// Creates rules for natural language. Only whitespace and punctuation separate words,
+    /// so `don't` and `iPhone` are single words.
+    pub fn prose() -> Self {
+        WordBoundary {
+            non_alphanumeric: false,
+            separators: vec![
+                ' ', '\t', '\n', '\r', '.', ',', ';', ':', '!', '?', '(', ')', '[', ']', '"', '/',
+            ],
+            case_change: false,
+            letter_digit: false,
+            unicode_words: false,
+        }
+    }
+
+    /// Creates the rules for identifiers (see [`WordBoundary::identifiers`]) with additional
+    /// word starts from Unicode text segmentation (see [`WordBoundary::unicode_words`]).
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn unicode() -> Self {
+        WordBoundary {
+            unicode_words: true,
+            ..WordBoundary::identifiers()
+        }
+    }
+
+    /// Returns `true` if `c` separates words.
+    pub fn is_separator(&self, c: char) -> bool {
+        (self.non_alphanumeric && !c.is_alphanumeric()) || self.separators.contains(&c)
+    }
+}
+
+impl Default for WordBoundary {
+    /// Creates the default rules, see [`WordBoundary::identifiers`].
+    fn default() -> Self {
+        WordBoundary::identifiers()
+    }
+}
+
+/// Kinds of word starts, see [`WordStartBonuses`](crate::WordStartBonuses).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WordStart {
+    /// First char of the string
+    String,
+    /// After `/` or `\\`
+    PathSeparator,
+    /// After `_` or `-`
+    Joiner,
+    /// After `.` or `:` (as in `::`)
+    Member,
+    /// After any other separator
+    Separator,
+    /// Uppercase char after a lowercase one or at the end of an uppercase run
+    CaseChange,
+    /// Letter/digit transitions and Unicode word boundaries
+    Other,
+}
+
+impl WordStart {
+    /// Returns the kind of a word start following the separator `sep`.
+    pub(crate) fn after_separator(sep: char) -> Self {
+        match sep {
+            '/' | '\\' => WordStart::PathSeparator,
+            '_' | '-' => WordStart::Joiner,
+            '.' | ':' => WordStart::Member,
+            _ => WordStart::Separator,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WordBoundary;
+
+    #[test]
+    fn presets() {
+        let identifiers = WordBoundary::identifiers();
+        let paths = WordBoundary::paths();
+        let prose = WordBoundary::prose();
+
+        assert!(identifiers.is_separator('\''));
+        assert!(!prose.is_separator('\''));
+        assert!(!prose.is_separator('-'));
+        assert!(paths.is_separator('\\'));
+        assert!(!paths.is_separator(':'));
+
+        for boundary in &[identifiers, paths, prose] {
+            assert!(boundary.is_separator(' '));
+            assert!(boundary.is_separator('/'));
+            assert!(!boundary.is_separator('a'));
+            assert!(!boundary.is_separator('7'));
+        }
+    }
+
+    #[test]
+    fn runtime_separators() {
+        let config = String::from("+~");
+        let boundary = WordBoundary {
+            separators: config.chars().collect(),
+            ..WordBoundary::prose()
+        };
+
+        assert!(boundary.is_separator('+'));
+        assert!(boundary.is_separator('~'));
+        assert!(!boundary.is_separator(' '));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{CharMatch, Match, ScoreBreakdown};
+
+/// How the score of a match was computed, see
+/// [`FuzzySearch::explain`](crate::FuzzySearch::explain).
+///
+/// The scores of all matched chars sum up to the score of the match.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use sublime_fuzzy::FuzzySearch;
+///
+/// let explanation = FuzzySearch::new("scc", "SoccerCartoonController")
+///     .explain()
+///     .unwrap();
+///
+/// for c in explanation.chars() {
+///     println!("{} {:?}", c.char_idx(), c.breakdown());
+/// }
+///
+/// assert_eq!(explanation.chars()[1].breakdown().word_start, 72);
+/// assert_eq!(explanation.score(), explanation.best_match().score());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Explanation {
+    m: Match,
+    chars: Vec<CharScore>,
+}
+
+impl Explanation {
+    pub(crate) fn new(m: Match, chars: Vec<CharScore>) -> Self {
+        Explanation { m, chars }
+    }
+
+    /// Returns the explained match.
+    pub fn best_match(&self) -> &Match {
+        &self.m
+    }
+
+    /// Returns the explained match.
+    pub fn into_match(self) -> Match {
+        self.m
+    }
+
+    /// Returns the matched chars in target order, like [`Match::matched_indices`].
+    ///
+    /// Query chars matched swapped (see [`Typo::Swapped`](crate::Typo::Swapped)) are not in
+    /// query order, [`CharMatch::query_idx`](crate::CharMatch::query_idx) returns the query
+    /// char of a matched char.
+    pub fn chars(&self) -> &[CharScore] {
+        &self.chars
+    }
+
+    /// Returns the sum of the scores of all matched chars.
+    pub fn score(&self) -> isize {
+        self.chars.iter().map(|c| c.score()).sum()
+    }
+}
+
+/// The score of a single matched char, see [`Explanation::chars`].
+#[derive(Clone, Debug)]
+pub struct CharScore {
+    pub(crate) char_match: CharMatch,
+    pub(crate) char_idx: usize,
+    pub(crate) breakdown: ScoreBreakdown,
+}
+
+impl CharScore {
+    /// Returns the matched char as passed to the [`Scorer`](crate::Scorer).
+    pub fn char_match(&self) -> &CharMatch {
+        &self.char_match
+    }
+
+    /// Returns the index of the matched char in the target string (the first char if
+    /// grapheme clusters are matched).
+    pub fn char_idx(&self) -> usize {
+        self.char_idx
+    }
+
+    /// Returns the bonuses/penalties applied for this char.
+    pub fn breakdown(&self) -> &ScoreBreakdown {
+        &self.breakdown
+    }
+
+    /// Returns the score of this char.
+    pub fn score(&self) -> isize {
+        self.breakdown.total()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FuzzySearch, Scoring};
+
+    #[test]
+    fn sums_up_to_score() {
+        let targets = [
+            "SoccerCartoonController",
+            "some search thing",
+            "scsc_ccs scc",
+            "src/scoring/sc_cache.rs",
+            "SCCsccSCC",
+        ];
+        let scoring = Scoring {
+            bonus_prefix: 24,
+            penalty_leading: 2,
+            ..Scoring::default()
+        };
+
+        for target in &targets {
+            for query in &["scc", "sc", "SCc", "ccs", "s", "csc", "sxcc"] {
+                let searches = vec![
+                    FuzzySearch::new(query, target),
+                    FuzzySearch::new(query, target).path_aware(),
+                    FuzzySearch::new(query, target).smart_case(),
+                    FuzzySearch::new(query, target).score_with(&scoring),
+                    FuzzySearch::new(query, target).max_typos(1),
+                ];
+
+                for search in searches {
+                    let m = search.clone().best_match();
+                    let explanation = search.explain();
+
+                    assert_eq!(
+                        explanation.as_ref().map(|e| e.score()),
+                        m.as_ref().map(|m| m.score()),
+                        "Differs from score for {} in {}",
+                        query,
+                        target
+                    );
+
+                    if let (Some(explanation), Some(m)) = (explanation, m) {
+                        assert!(explanation.best_match().structural_eq(&m));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn breakdown() {
+        let explanation = FuzzySearch::new("sca", "SoccerCartoon").explain().unwrap();
+        let chars = explanation.chars();
+
+        assert_eq!(
+            chars.iter().map(|c| c.char_idx()).collect::<Vec<usize>>(),
+            vec![0, 6, 7]
+        );
+
+        assert_eq!(chars[0].breakdown().word_start, 72);
+        assert_eq!(chars[0].breakdown().match_case, 0);
+        assert_eq!(chars[1].breakdown().gap, -20);
+        assert_eq!(chars[2].breakdown().word_start, 0);
+        assert_eq!(chars[2].breakdown().match_case, 8);
+        assert!(chars[2].breakdown().consecutive > 0);
+    }
+
+    #[test]
+    fn target_order() {
+        let explanation = FuzzySearch::new("bac", "abc")
+            .max_typos(1)
+            .explain()
+            .unwrap();
+        let chars = explanation.chars();
+
+        assert_eq!(
+            chars.iter().map(|c| c.char_idx()).collect::<Vec<usize>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            chars
+                .iter()
+                .map(|c| c.char_match().query_idx())
+                .collect::<Vec<usize>>(),
+            vec![1, 0, 2]
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::mem;
+
+use crate::{pattern::Pattern, ranking::Ranked, target::PreparedTarget, FuzzySearch, Scorer};
+
+/// Searches a fixed list of candidates with a query that changes between searches,
+/// like the query of a search box while the user is typing.
+///
+/// Candidates are prepared once (see [`PreparedTarget`]). If the new query extends the
+/// previous one (all chars of the previous query appear in the same order, e.g. `ab` -> `abc`
+/// or `ac` -> `abc`), only the candidates that matched the previous query are matched again.
+/// Any other change, like deleting a char, falls back to matching all candidates.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use sublime_fuzzy::IncrementalSearch;
+///
+/// let mut search = IncrementalSearch::new(vec!["src/lib.rs", "src/search.rs", "README.md"]);
+///
+/// assert_eq!(search.search("s").len(), 2);
+/// assert_eq!(search.search("se").len(), 1);
+/// assert_eq!(search.search("r").len(), 3);
+/// ```
+pub struct IncrementalSearch<'a, T, S: ?Sized = dyn Scorer + Sync + 'a> {
+    search: FuzzySearch<'a, S>,
+    candidates: Vec<T>,
+    prepared: Vec<PreparedTarget>,
+    previous: Option<Pattern<'a, S>>,
+    survivors: Vec<usize>,
+}
+
+impl<'a, T: AsRef<str>> IncrementalSearch<'a, T> {
+    /// Creates a new incremental search over `candidates` with the default configuration
+    /// (case insensitive, default scoring).
+    pub fn new(candidates: Vec<T>) -> Self {
+        IncrementalSearch::with_search(FuzzySearch::with_query(""), candidates)
+    }
+}
+
+impl<'a, T: AsRef<str>, S: Scorer + ?Sized> IncrementalSearch<'a, T, S> {
+    /// Creates a new incremental search over `candidates` which matches with the configuration
+    /// of `search` (case sensitivity, scoring, ...). The query and target string of `search`
+    /// are not used.
+    pub fn with_search(search: FuzzySearch<'a, S>, candidates: Vec<T>) -> Self {
+        let prepared = candidates
+            .iter()
+            .map(|c| search.prepare(c.as_ref()))
+            .collect();
+
+        IncrementalSearch {
+            search,
+            candidates,
+            prepared,
+            previous: None,
+            survivors: Vec::new(),
+        }
+    }
+
+    /// Returns the searched candidates.
+    pub fn candidates(&self) -> &[T] {
+        &self.candidates
+    }
+
+    /// Matches `query` against the candidates and returns the matching ones, best match first.
+    ///
+    /// Candidates are ordered by the ranking of the search (see [`FuzzySearch::rank_by`]),
+    /// by default candidates with equal scores keep their input order.
+    pub fn search(&mut self, query: &str) -> Vec<Ranked<&T>> {
+        self.search_limited(query, None)
+    }
+
+    /// Like [`IncrementalSearch::search`], but only returns the best `limit` matches.
+    ///
+    /// All matching candidates are still remembered for narrowing the next search.
+    pub fn search_top(&mut self, query: &str, limit: usize) -> Vec<Ranked<&T>> {
+        self.search_limited(query, Some(limit))
+    }
+
+    /// Forgets the previous query, the next search will match all candidates.
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.survivors.clear();
+    }
+
+    fn search_limited(&mut self, query: &str, limit: Option<usize>) -> Vec<Ranked<&T>> {
+        let mut pattern = self.search.compile_query(query);
+
+        // Weak matches can get better with a longer query, so they still narrow the next search
+        // and are only left out of the results
+        let min_score = pattern.min_score().unwrap_or(isize::MIN);
+        pattern.set_min_score(None);
+
+        let narrow = self
+            .previous
+            .as_ref()
+            .is_some_and(|previous| pattern.narrows(previous));
+
+        let previous_survivors = mem::take(&mut self.survivors);
+
+        let mut collector = pattern.rank_collector(limit);
+        let mut survivors = Vec::new();
+
+        {
+            let prepared = &self.prepared;
+
+            let mut match_candidate = |i: usize| {
+                if let Some(m) = pattern.best_match_prepared(&prepared[i]) {
+                    survivors.push(i);
+
+                    if m.score() >= min_score {
+                        collector.push(Ranked::new(i, i, m));
+                    }
+                }
+            };
+
+            if narrow {
+                previous_survivors
+                    .into_iter()
+                    .for_each(&mut match_candidate);
+            } else {
+                (0..prepared.len()).for_each(&mut match_candidate);
+            }
+        }
+
+        self.survivors = survivors;
+        self.previous = Some(pattern);
+
+        let candidates = &self.candidates;
+
+        collector
+            .into_sorted_vec()
+            .into_iter()
+            .map(|r| {
+                let (i, m) = r.into_parts();
+
+                Ranked::new(i, &candidates[i], m)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FuzzySearch, Ranked};
+
+    use super::IncrementalSearch;
+
+    fn indices(ranked: Vec<Ranked<&&str>>) -> Vec<usize> {
+        ranked.iter().map(|r| r.index()).collect()
+    }
+
+    fn full_pass(query: &str, candidates: &[&str]) -> Vec<usize> {
+        FuzzySearch::with_query(query)
+            .rank(candidates)
+            .iter()
+            .map(|r| r.index())
+            .collect()
+    }
+
+    #[test]
+    fn narrows_like_full_pass() {
+        let candidates = vec!["abc", "a_b_c", "acb", "xyz", "ab", "bca", "a"];
+
+        let mut search = IncrementalSearch::new(candidates.clone());
+
+        for query in &["a", "ab", "abc", "ab", "ac", "abc", "", "b", "bc"] {
+            assert_eq!(
+                indices(search.search(query)),
+                full_pass(query, &candidates),
+                "Differs from full pass for {}",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn narrows_only_survivors() {
+        let mut search = IncrementalSearch::new(vec!["ab", "ba"]);
+
+        assert_eq!(search.search("a").len(), 2);
+        assert_eq!(search.search("ab").len(), 1);
+        assert_eq!(search.survivors, vec![0]);
+
+        assert_eq!(search.search("b").len(), 2, "Deletion not matched fully");
+    }
+
+    #[test]
+    fn case_sensitive() {
+        let mut search = IncrementalSearch::with_search(
+            FuzzySearch::with_query("").case_sensitive(),
+            vec!["Ab", "ab"],
+        );
+
+        assert_eq!(indices(search.search("A")), vec![0]);
+        assert_eq!(indices(search.search("Ab")), vec![0]);
+        assert_eq!(indices(search.search("a")), vec![1]);
+    }
+
+    #[test]
+    fn min_score_keeps_weak_survivors() {
+        let mut search = IncrementalSearch::with_search(
+            FuzzySearch::with_query("").min_score(150),
+            vec!["xaxbxc", "abc", "a_b_c"],
+        );
+
+        assert_eq!(indices(search.search("ab")), vec![2]);
+        assert_eq!(search.survivors, vec![0, 1, 2]);
+        assert_eq!(indices(search.search("abc")), vec![2, 1]);
+    }
+
+    #[test]
+    fn max_gap_narrows_like_full_pass() {
+        let candidates = vec!["a__c", "a_b_c", "ac", "abc", "xa_c"];
+
+        let mut search = IncrementalSearch::with_search(
+            FuzzySearch::with_query("").max_gap(1),
+            candidates.clone(),
+        );
+
+        for query in &["a", "ac", "abc", "xac", "xabc", "ab"] {
+            let full_pass = FuzzySearch::with_query(query)
+                .max_gap(1)
+                .rank(&candidates)
+                .iter()
+                .map(|r| r.index())
+                .collect::<Vec<usize>>();
+
+            assert_eq!(
+                indices(search.search(query)),
+                full_pass,
+                "Differs from full pass for {}",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn typos_narrow_like_full_pass() {
+        // Candidates without the first query chars only match if those are skipped as typos
+        let candidates = vec![
+            "abc", "acb", "ab", "bca", "xyz", "a_c", "bc", "c", "cb", "b_d",
+        ];
+
+        for max_typos in 1..3 {
+            let mut search = IncrementalSearch::with_search(
+                FuzzySearch::with_query("").max_typos(max_typos),
+                candidates.clone(),
+            );
+
+            for query in &[
+                "a", "ab", "abc", "abcd", "abcde", "bac", "ac", "b", "bd", "abd", "xabd",
+            ] {
+                let full_pass = FuzzySearch::with_query(query)
+                    .max_typos(max_typos)
+                    .rank(&candidates)
+                    .iter()
+                    .map(|r| r.index())
+                    .collect::<Vec<usize>>();
+
+                assert_eq!(
+                    indices(search.search(query)),
+                    full_pass,
+                    "Differs from full pass for {} with {} typos",
+                    query,
+                    max_typos
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn smart_case_narrows_like_full_pass() {
+        let candidates = vec!["abc", "aBc", "ABC", "a_b_C", "Abc"];
+        let search = FuzzySearch::with_query("").smart_case_per_char();
+
+        let mut incremental = IncrementalSearch::with_search(search.clone(), candidates.clone());
+
+        for query in &["a", "aB", "aBc", "ab", "abC", "AbC", "Ab"] {
+            let full_pass = FuzzySearch::with_query(query)
+                .smart_case_per_char()
+                .rank(&candidates)
+                .iter()
+                .map(|r| r.index())
+                .collect::<Vec<usize>>();
+
+            assert_eq!(
+                indices(incremental.search(query)),
+                full_pass,
+                "Differs from full pass for {}",
+                query
+            );
+        }
+    }
+}
@@ -69,23 +69,53 @@
 //! assert!(result.is_some())
 //! ```
 //!
+//! A list of candidates can be ranked in one go, optionally keeping only the best matches:
+//!
+//! ```rust
+//! use sublime_fuzzy::FuzzySearch;
+//!
+//! let files = vec!["src/lib.rs", "src/search.rs", "README.md"];
+//!
+//! let ranked = FuzzySearch::with_query("sr").rank_top(files, 1);
+//!
+//! assert_eq!(*ranked[0].candidate(), "src/search.rs");
+//! ```
+//!
 //! **Note:** Any whitespace in the pattern (`'something'`
 //! in the examples above) will be removed.
 //!
+#[cfg(feature = "rayon")]
+extern crate rayon;
 #[cfg(feature = "serde_support")]
 extern crate serde;
 #[cfg(feature = "serde_support")]
 #[macro_use]
 extern crate serde_derive;
-
+#[cfg(feature = "unicode-normalization")]
+extern crate unicode_normalization;
+#[cfg(feature = "unicode-segmentation")]
+extern crate unicode_segmentation;
+
+mod boundary;
+mod explain;
+mod incremental;
 mod matching;
 mod parsing;
+mod pattern;
+mod ranking;
 mod scoring;
 mod search;
-
-pub use matching::{ContinuousMatch, ContinuousMatches, Match};
-pub use scoring::Scoring;
+mod target;
+
+pub use boundary::{WordBoundary, WordStart};
+pub use explain::{CharScore, Explanation};
+pub use incremental::IncrementalSearch;
+pub use matching::{ContinuousMatch, ContinuousMatches, Match, Typo};
+pub use pattern::Pattern;
+pub use ranking::{RankKey, Ranked, Ranking};
+pub use scoring::{CharMatch, ScoreBreakdown, Scorer, Scoring, WordStartBonuses};
 pub use search::FuzzySearch;
+pub use target::PreparedTarget;
 
 /// Returns the best match for `query` in the target string `string`.
 ///
@@ -115,6 +145,34 @@ pub fn best_match(query: &str, target: &str) -> Option<Match> {
         .best_match()
 }
 
+/// Returns all `candidates` that match `query`, best match first.
+///
+/// Candidates with equal scores keep their input order. See [`FuzzySearch::rank`]
+/// for a configurable version and [`FuzzySearch::rank_top`] to limit the number of results.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use sublime_fuzzy::rank;
+///
+/// let ranked = rank("TT", vec!["the two towers", "tower", "Two Towers"]);
+///
+/// assert_eq!(ranked.len(), 2);
+/// assert_eq!(*ranked[0].candidate(), "Two Towers");
+/// ```
+///
+pub fn rank<I>(query: &str, candidates: I) -> Vec<Ranked<I::Item>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    FuzzySearch::with_query(query)
+        .case_insensitive()
+        .rank(candidates)
+}
+
 /// Formats a [`Match`] by appending `before` before any matches and `after`
 /// after any matches.
 ///
@@ -135,46 +193,43 @@ pub fn best_match(query: &str, target: &str) -> Option<Match> {
 /// ```
 ///
 pub fn format_simple(match_: &Match, target: &str, before: &str, after: &str) -> String {
-    let str_before = before.to_owned();
-    let str_after = after.to_owned();
-
     let mut pieces = Vec::new();
 
+    let byte_ranges = match_.byte_ranges(target);
+
     let mut last_end = 0;
 
-    for c in match_.continuous_matches() {
+    for c in match_.continuous_matches_with(&byte_ranges) {
+        let bytes = c.byte_range();
+
         // Piece between last match and this match
-        pieces.push(
-            target
-                .chars()
-                .skip(last_end)
-                .take(c.start() - last_end)
-                .collect::<String>(),
-        );
+        pieces.push(&target[last_end..bytes.start]);
 
-        pieces.push(str_before.clone());
+        pieces.push(before);
 
         // This match
-        pieces.push(target.chars().skip(c.start()).take(c.len()).collect());
+        pieces.push(&target[bytes.clone()]);
 
-        pieces.push(str_after.clone());
+        pieces.push(after);
 
-        last_end = c.start() + c.len();
+        last_end = bytes.end;
     }
 
     // Leftover chars
-    if last_end != target.len() {
-        pieces.push(target.chars().skip(last_end).collect::<String>());
-    }
+    pieces.push(&target[last_end..]);
 
     pieces.join("")
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{best_match, format_simple, matching::ContinuousMatch};
+    use crate::{
+        best_match, format_simple, matching::ContinuousMatch, rank, CharMatch, FuzzySearch,
+        IncrementalSearch, Match, Scorer, Scoring, Typo,
+    };
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn feature_serde() {
         assert!(cfg!(feature = "serde_support"));
     }
@@ -206,6 +261,18 @@ mod tests {
         assert_eq!(best_match("partial", "part"), None);
     }
 
+    #[test]
+    fn equal_scores_match_last_occurrence() {
+        let m = best_match("ab", "x_ab_ab").unwrap();
+
+        assert_eq!(
+            m.matched_indices().cloned().collect::<Vec<usize>>(),
+            vec![5, 6]
+        );
+        assert_eq!(m, best_match("ab", "x_ab_").unwrap());
+        assert!(!m.structural_eq(&best_match("ab", "x_ab_xx").unwrap()));
+    }
+
     #[test]
     fn case_sensitivity() {
         assert!(
@@ -281,6 +348,379 @@ mod tests {
         assert_eq!(a.score(), b.score());
     }
 
+    #[test]
+    fn prefix_bonus_and_leading_penalty() {
+        let scoring = Scoring {
+            bonus_prefix: 16,
+            penalty_leading: 2,
+            ..Scoring::default()
+        };
+
+        let score = |target: &str| {
+            FuzzySearch::new("rel", target)
+                .score_with(&scoring)
+                .best_match()
+                .unwrap()
+                .score()
+        };
+        let default_score = |target: &str| best_match("rel", target).unwrap().score();
+
+        assert_eq!(score("release_notes"), default_score("release_notes") + 16);
+        assert_eq!(score("a_release"), default_score("a_release") - 2 * 2);
+        assert_eq!(score("some_release"), default_score("some_release") - 5 * 2);
+    }
+
+    #[test]
+    fn structural_eq() {
+        let a = best_match("ab", "a_b").unwrap();
+
+        assert!(a.structural_eq(&best_match("ab", "a_b").unwrap()));
+        assert!(!a.structural_eq(&best_match("ab", "a_b_").unwrap()));
+        assert!(!a.structural_eq(&best_match("ab", "ab").unwrap()));
+    }
+
+    #[test]
+    fn quality() {
+        let quality = |query: &str, target: &str| best_match(query, target).unwrap().quality();
+
+        assert_eq!(quality("a", "a"), 1.0);
+        assert!(quality("abc", "a-b-c") > quality("abc", "xaxbxc"));
+        assert!(quality("sc", "SoccerCartoon") > quality("sc", "discount"));
+
+        let m = FuzzySearch::new("ab", "a_b")
+            .path_aware()
+            .best_match()
+            .unwrap();
+
+        assert!(m.best_score() > best_match("ab", "a_b").unwrap().best_score());
+        assert!(m.quality() > 0.0 && m.quality() < 1.0);
+
+        let negative = Scoring {
+            penalty_distance: 1000,
+            ..Scoring::default()
+        };
+
+        assert_eq!(
+            FuzzySearch::new("ab", "a____b")
+                .score_with(&negative)
+                .best_match()
+                .unwrap()
+                .quality(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn min_score_prunes_only_weak_matches() {
+        let targets = [
+            "SoccerCartoonController",
+            "some search thing",
+            "s_c_c__s_c_c___scc",
+            "sxxxxxxxxxxxxxxxxxxxxxcxxxxxxxxxxxxxxxxxc_Scc",
+            "ssccsscc",
+            "src/scoring/sc_cache.rs",
+            "a_s_c_c_xscc",
+        ];
+        let scorings = [Scoring::default(), Scoring::emphasize_distance()];
+
+        for target in &targets {
+            for query in &["scc", "sc", "sccs", "s"] {
+                for scoring in &scorings {
+                    let full = FuzzySearch::new(query, target)
+                        .score_with(scoring)
+                        .best_match();
+
+                    for min in &[-100, 0, 50, 100, 150, 200, 250, 300, 400] {
+                        let pruned = FuzzySearch::new(query, target)
+                            .score_with(scoring)
+                            .min_score(*min)
+                            .best_match();
+
+                        let unpruned = full.clone().filter(|m| m.score() >= *min);
+
+                        assert!(
+                            match (&pruned, &unpruned) {
+                                (Some(pruned), Some(unpruned)) => pruned.structural_eq(unpruned),
+                                (pruned, unpruned) => pruned.is_none() && unpruned.is_none(),
+                            },
+                            "Differs from unpruned match for {} in {} with min score {}",
+                            query,
+                            target,
+                            min
+                        );
+                    }
+                }
+            }
+        }
+
+        assert_eq!(
+            FuzzySearch::new("scc", "SoccerCartoonController")
+                .min_score(172)
+                .best_match()
+                .map(|m| m.score()),
+            Some(172)
+        );
+        assert!(FuzzySearch::new("scc", "SoccerCartoonController")
+            .min_score(173)
+            .best_match()
+            .is_none());
+
+        // Skipping weak alignments must not lead to another one reaching the minimum
+        assert_eq!(
+            best_match("sc", "a_s_c_c_xscc").map(|m| m.score()),
+            Some(156)
+        );
+        assert!(FuzzySearch::new("sc", "a_s_c_c_xscc")
+            .min_score(157)
+            .best_match()
+            .is_none());
+    }
+
+    #[test]
+    fn max_gap() {
+        let matched = |target: &str, max_gap: usize| {
+            FuzzySearch::new("abc", target)
+                .max_gap(max_gap)
+                .best_match()
+                .map(|m| m.matched_indices().cloned().collect::<Vec<usize>>())
+        };
+
+        assert_eq!(matched("abc", 0), Some(vec![0, 1, 2]));
+        assert_eq!(matched("a_bc", 0), None);
+        assert_eq!(matched("a_bc", 1), Some(vec![0, 2, 3]));
+        // The closer alignment is used even if it scores worse
+        assert_eq!(matched("a_b______c_abc", 5), Some(vec![11, 12, 13]));
+        assert_eq!(matched("a_b______c_abc", 6), Some(vec![0, 2, 9]));
+    }
+
+    #[test]
+    fn typos() {
+        let matched = |query: &str, target: &str, max_typos: usize| {
+            FuzzySearch::new(query, target)
+                .max_typos(max_typos)
+                .best_match()
+                .map(|m| {
+                    (
+                        m.matched_indices().cloned().collect::<Vec<usize>>(),
+                        m.typos().cloned().collect::<Vec<Typo>>(),
+                    )
+                })
+        };
+
+        assert_eq!(matched("abxc", "abc", 0), None);
+        assert_eq!(
+            matched("abxc", "abc", 1),
+            Some((vec![0, 1, 2], vec![Typo::Missing(2)]))
+        );
+        assert_eq!(
+            matched("abcx", "abc", 1),
+            Some((vec![0, 1, 2], vec![Typo::Missing(3)]))
+        );
+        assert_eq!(
+            matched("bac", "abc", 1),
+            Some((vec![0, 1, 2], vec![Typo::Swapped(0)]))
+        );
+        assert_eq!(matched("xbyc", "abc", 1), None);
+        assert_eq!(
+            matched("xbyc", "abc", 2),
+            Some((vec![1, 2], vec![Typo::Missing(0), Typo::Missing(2)]))
+        );
+        // Exact matches aren't replaced by ones with typos
+        assert_eq!(matched("abc", "abc", 2), Some((vec![0, 1, 2], vec![])));
+
+        let m = FuzzySearch::new("abxc", "abc")
+            .max_typos(1)
+            .best_match()
+            .unwrap();
+
+        assert_eq!(m.unmatched_query_indices().collect::<Vec<usize>>(), vec![2]);
+        assert_eq!(
+            m.score(),
+            FuzzySearch::new("abc", "abc").best_match().unwrap().score()
+                - Scoring::default().penalty_typo
+        );
+    }
+
+    #[test]
+    fn typos_never_lower_score() {
+        let long_gap = format!("ab{}c", "_".repeat(30));
+        let targets = [
+            "SoccerCartoonController",
+            "some search thing",
+            "s_c_c__s_c_c___scc",
+            "ssccsscc",
+            "src/scoring/sc_cache.rs",
+            long_gap.as_str(),
+        ];
+
+        for target in &targets {
+            for query in &["scc", "sc", "sccs", "abc", "cs", "sst"] {
+                let exact = FuzzySearch::new(query, target)
+                    .best_match()
+                    .map(|m| m.score());
+
+                for max_typos in 1..3 {
+                    let with_typos = FuzzySearch::new(query, target)
+                        .max_typos(max_typos)
+                        .best_match()
+                        .map(|m| m.score());
+
+                    assert!(
+                        with_typos >= exact,
+                        "Lower score with {} typos for {} in {}",
+                        max_typos,
+                        query,
+                        target
+                    );
+                }
+            }
+        }
+
+        // Skipping `b` avoids no gap, the gap to `c` is only moved
+        assert_eq!(
+            FuzzySearch::new("abc", &long_gap)
+                .max_typos(1)
+                .best_match()
+                .map(|m| m.matched_indices().cloned().collect::<Vec<usize>>()),
+            Some(vec![0, 1, 32])
+        );
+    }
+
+    /// Affine gap costs: opening a gap costs more than extending it.
+    struct AffineGaps;
+
+    impl Scorer for AffineGaps {
+        fn score_char(&self, _c: &CharMatch) -> isize {
+            10
+        }
+
+        fn score_consecutive(&self, _run: usize) -> isize {
+            0
+        }
+
+        fn score_gap(&self, gap: usize) -> isize {
+            -8 - gap as isize
+        }
+    }
+
+    #[test]
+    fn custom_scorer() {
+        let score = |query: &str, target: &str| {
+            FuzzySearch::new(query, target)
+                .score_with(&AffineGaps)
+                .best_match()
+                .unwrap()
+                .score()
+        };
+
+        assert_eq!(score("abc", "abc"), 30);
+        assert_eq!(score("abc", "a__bc"), 30 - 10);
+        assert_eq!(score("abc", "a_b_c"), 30 - 18);
+
+        let scoring = Scoring::default();
+
+        assert_eq!(
+            FuzzySearch::new("scc", "SoccerCartoonController")
+                .score_with(&scoring)
+                .best_match(),
+            best_match("scc", "SoccerCartoonController")
+        );
+    }
+
+    /// Scores every consecutive char with 1 and records the runs it was called with.
+    struct ConsecutiveRuns(std::sync::Mutex<Vec<usize>>);
+
+    impl Scorer for ConsecutiveRuns {
+        fn score_char(&self, _c: &CharMatch) -> isize {
+            0
+        }
+
+        fn score_consecutive(&self, run: usize) -> isize {
+            self.0.lock().unwrap().push(run);
+
+            1
+        }
+
+        fn score_gap(&self, _gap: usize) -> isize {
+            0
+        }
+    }
+
+    #[test]
+    fn consecutive_scored_once_per_char() {
+        let scorer = ConsecutiveRuns(std::sync::Mutex::new(Vec::new()));
+
+        let explanation = FuzzySearch::new("abcd", "abcd")
+            .score_with(&scorer)
+            .explain()
+            .unwrap();
+
+        assert_eq!(explanation.score(), 3);
+        assert_eq!(
+            explanation
+                .chars()
+                .iter()
+                .map(|c| c.breakdown().consecutive)
+                .collect::<Vec<isize>>(),
+            vec![0, 1, 1, 1]
+        );
+        assert!(scorer.0.lock().unwrap().iter().all(|&run| run <= 3));
+
+        // The default scoring adds up to the same bonus as earlier versions
+        let bonus = Scoring::default().bonus_consecutive;
+
+        assert_eq!(
+            FuzzySearch::new("abcd", "abcd")
+                .explain()
+                .unwrap()
+                .chars()
+                .iter()
+                .map(|c| c.breakdown().consecutive)
+                .collect::<Vec<isize>>(),
+            vec![0, 2 * bonus, 6 * bonus, (12 + 9) * bonus]
+        );
+    }
+
+    /// Counts the scored chars in a `Cell`, which is not `Sync`.
+    struct CountingScorer(std::cell::Cell<usize>);
+
+    impl Scorer for CountingScorer {
+        fn score_char(&self, _c: &CharMatch) -> isize {
+            self.0.set(self.0.get() + 1);
+
+            1
+        }
+
+        fn score_consecutive(&self, _run: usize) -> isize {
+            0
+        }
+
+        fn score_gap(&self, _gap: usize) -> isize {
+            0
+        }
+    }
+
+    #[test]
+    fn scorer_without_sync() {
+        let scorer = CountingScorer(std::cell::Cell::new(0));
+
+        let m = FuzzySearch::new("ab", "ab")
+            .score_with(&scorer)
+            .best_match()
+            .unwrap();
+
+        assert_eq!(m.score(), 2);
+        assert!(scorer.0.get() >= 2);
+
+        let mut search = IncrementalSearch::with_search(
+            FuzzySearch::with_query("").score_with(&scorer),
+            vec!["ab", "b"],
+        );
+
+        assert_eq!(search.search("b").len(), 2);
+        assert_eq!(search.search("ab").len(), 1);
+    }
+
     #[test]
     fn matches_unicode() {
         let m = best_match("👀", "🦀 👈 👀").unwrap();
@@ -298,4 +738,274 @@ mod tests {
 
         assert_eq!(format_simple(&m, s, "<", ">"), "🦀 👈 <👀>");
     }
+
+    #[test]
+    fn formats_without_byte_ranges() {
+        let s = "🦀 some 👀 thing";
+        let m = Match::with_matched(0, 0, vec![2, 3, 9]);
+
+        assert_eq!(m.matched_byte_ranges().len(), 0);
+        assert_eq!(format_simple(&m, s, "<", ">"), "🦀 <so>me 👀 <t>hing");
+    }
+
+    #[test]
+    #[cfg(feature = "serde_support")]
+    fn deserialized_without_byte_ranges() {
+        let target = "🦀 crab";
+        let m: Match =
+            serde_json::from_str(r#"{"score": 0, "consecutive": 0, "matched": [2, 5]}"#).unwrap();
+
+        assert_eq!(m.matched_utf16_ranges(target), vec![3..4, 6..7]);
+        assert_eq!(m.continuous_utf16_ranges(target), vec![3..4, 6..7]);
+        assert_eq!(format_simple(&m, target, "<", ">"), "🦀 <c>ra<b>");
+    }
+
+    #[test]
+    fn ranks_matching_candidates() {
+        let ranked = rank("rel", vec!["rlx", "nothing", "r_e_l", "rel", "a_release"]);
+
+        assert_eq!(
+            ranked.iter().map(|r| r.index()).collect::<Vec<usize>>(),
+            vec![2, 3, 4],
+            "Not sorted by score or ties not in input order"
+        );
+    }
+
+    #[test]
+    fn rank_top_matches_rank() {
+        let candidates = vec![
+            "some_thing",
+            "something",
+            "some search thing",
+            "sth",
+            "other",
+        ];
+
+        let all = FuzzySearch::with_query("sth").rank(candidates.clone());
+        let top = FuzzySearch::with_query("sth").rank_top(candidates, 2);
+
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].index(), all[0].index());
+        assert_eq!(top[1].index(), all[1].index());
+    }
+
+    #[test]
+    fn byte_ranges_unicode() {
+        let s = "🦀 Ünïcödé 👀";
+        let m = best_match("üd👀", s).unwrap();
+
+        assert_eq!(
+            m.matched_byte_ranges()
+                .map(|r| &s[r.clone()])
+                .collect::<Vec<&str>>(),
+            vec!["Ü", "d", "👀"]
+        );
+        assert_eq!(
+            m.continuous_matches()
+                .map(|c| &s[c.byte_range()])
+                .collect::<Vec<&str>>(),
+            vec!["Ü", "d", "👀"]
+        );
+    }
+
+    #[test]
+    fn utf16_ranges() {
+        let s = "🦀 👈 Some Thing 👀";
+        let m = best_match("st👀", s).unwrap();
+
+        let utf16 = s.encode_utf16().collect::<Vec<u16>>();
+        let slice = |r: &std::ops::Range<usize>| String::from_utf16(&utf16[r.clone()]).unwrap();
+
+        assert_eq!(
+            m.matched_utf16_ranges(s)
+                .iter()
+                .map(slice)
+                .collect::<Vec<String>>(),
+            vec!["S", "T", "👀"]
+        );
+        assert_eq!(m.continuous_utf16_ranges(s), vec![6..7, 11..12, 17..19]);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-segmentation")]
+    fn grapheme_clusters_not_split() {
+        let s = "cafe\u{301} eclair";
+
+        let m = FuzzySearch::new("e", s)
+            .grapheme_clusters()
+            .best_match()
+            .unwrap();
+
+        assert_eq!(
+            m.matched_indices().cloned().collect::<Vec<usize>>(),
+            vec![6]
+        );
+
+        let m = FuzzySearch::new("ce\u{301}", s)
+            .grapheme_clusters()
+            .best_match()
+            .unwrap();
+
+        assert_eq!(format_simple(&m, s, "<", ">"), "<c>af<e\u{301}> eclair");
+        assert_eq!(
+            m.continuous_matches()
+                .map(|c| (c.start(), c.len()))
+                .collect::<Vec<(usize, usize)>>(),
+            vec![(0, 1), (3, 2)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-normalization")]
+    fn accent_insensitive() {
+        let matched = |query: &str, target: &str| {
+            FuzzySearch::new(query, target)
+                .accent_insensitive()
+                .best_match()
+                .map(|m| format_simple(&m, target, "<", ">"))
+        };
+
+        assert_eq!(matched("cafe", "Café.md").as_deref(), Some("<Café>.md"));
+        assert_eq!(matched("naive", "naïve").as_deref(), Some("<naïve>"));
+        assert_eq!(matched("Zurich", "Zürich").as_deref(), Some("<Zürich>"));
+        assert_eq!(matched("zürich", "Zurich").as_deref(), Some("<Zurich>"));
+        assert!(FuzzySearch::new("cafe", "Café.md").best_match().is_none());
+
+        // Decomposed accents are kept with their base char
+        let decomposed = "Cafe\u{301}";
+
+        assert_eq!(
+            matched("cafe", decomposed).as_deref(),
+            Some("<Cafe\u{301}>")
+        );
+        assert_eq!(
+            FuzzySearch::new("cafe", decomposed)
+                .accent_insensitive()
+                .best_match()
+                .map(|m| m.target_len()),
+            Some(5)
+        );
+
+        let exact = FuzzySearch::new("café", "café")
+            .accent_insensitive()
+            .best_match();
+        let folded = FuzzySearch::new("cafe", "café")
+            .accent_insensitive()
+            .best_match();
+
+        assert!(exact.unwrap().score() > folded.unwrap().score());
+    }
+
+    #[test]
+    #[cfg(all(feature = "unicode-normalization", feature = "unicode-segmentation"))]
+    fn accent_insensitive_grapheme_clusters() {
+        let s = "Cafe\u{301}";
+
+        let m = FuzzySearch::new("cafe", s)
+            .accent_insensitive()
+            .grapheme_clusters()
+            .best_match()
+            .unwrap();
+
+        assert_eq!(format_simple(&m, s, "<", ">"), "<Cafe\u{301}>");
+    }
+
+    #[test]
+    fn multi_char_case_folding() {
+        let highlighted = |query: &str, target: &str| {
+            best_match(query, target).map(|m| format_simple(&m, target, "<", ">"))
+        };
+
+        // Turkish `İ` lowercases to two chars
+        assert_eq!(highlighted("ul", "İstanbul").as_deref(), Some("İstanb<ul>"));
+        assert_eq!(
+            highlighted("ist", "İstanbul").as_deref(),
+            Some("<İst>anbul")
+        );
+        // Final sigma
+        assert_eq!(
+            highlighted("οδυσσευσ", "ΟΔΥΣΣΕΥΣ").as_deref(),
+            Some("<ΟΔΥΣΣΕΥΣ>")
+        );
+        assert_eq!(highlighted("ΣΣ", "οδυσσευς").as_deref(), Some("οδυ<σσ>ευς"));
+        assert_eq!(highlighted("σς", "ΣΕΥΣ").as_deref(), Some("<Σ>ΕΥ<Σ>"));
+        // Ligatures stay single chars
+        assert_eq!(highlighted("ﬂow", "ﬁle_ﬂow").as_deref(), Some("ﬁle_<ﬂow>"));
+        assert_eq!(highlighted("ß", "STRAẞE").as_deref(), Some("STRA<ẞ>E"));
+    }
+
+    #[test]
+    fn smart_case() {
+        let matches = |query: &str, target: &str| {
+            FuzzySearch::new(query, target)
+                .smart_case()
+                .best_match()
+                .is_some()
+        };
+
+        assert!(matches("scc", "SoccerCartoonController"));
+        assert!(matches("SCC", "SoccerCartoonController"));
+        assert!(!matches("SCc", "SoccerCartoonController"));
+        assert!(matches("SCc", "Soccer Cartoon controller"));
+    }
+
+    #[test]
+    fn smart_case_per_char() {
+        let highlighted = |query: &str, target: &str| {
+            FuzzySearch::new(query, target)
+                .smart_case_per_char()
+                .best_match()
+                .map(|m| format_simple(&m, target, "<", ">"))
+        };
+
+        assert_eq!(
+            highlighted("sC", "some_scheme_Config").as_deref(),
+            Some("some_<s>cheme_<C>onfig")
+        );
+        assert_eq!(highlighted("Sc", "xs_Sc").as_deref(), Some("xs_<Sc>"));
+        assert_eq!(highlighted("sc", "xs_Sc").as_deref(), Some("xs_<Sc>"));
+        assert!(highlighted("sC", "xs_Sc").is_none());
+    }
+
+    #[test]
+    fn path_aware_prefers_basename() {
+        let files = ["lib/src/main.rs", "src/lib.rs"];
+
+        let first = |search: FuzzySearch| *search.rank(&files)[0].candidate();
+
+        assert_eq!(first(FuzzySearch::with_query("lib")), &"lib/src/main.rs");
+        assert_eq!(
+            first(FuzzySearch::with_query("lib").path_aware()),
+            &"src/lib.rs"
+        );
+    }
+
+    #[test]
+    fn path_aware_weights() {
+        let mut scoring = Scoring::default();
+
+        let score = |scoring: &Scoring| {
+            FuzzySearch::new("sm", "src/main.rs")
+                .score_with(scoring)
+                .path_aware()
+                .best_match()
+                .unwrap()
+                .score()
+        };
+        let default_score = FuzzySearch::new("sm", "src/main.rs")
+            .best_match()
+            .unwrap()
+            .score();
+
+        assert_eq!(
+            score(&scoring) - default_score,
+            scoring.bonus_segment_start + scoring.bonus_basename + scoring.bonus_basename_end
+        );
+
+        scoring.bonus_segment_start = 0;
+        scoring.bonus_basename = 0;
+        scoring.bonus_basename_end = 0;
+
+        assert_eq!(score(&scoring), default_score);
+    }
 }
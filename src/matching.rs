@@ -1,6 +1,6 @@
-use std::{cmp::Ordering, slice::Iter};
+use std::{borrow::Cow, cmp::Ordering, ops::Range, slice::Iter};
 
-use crate::Scoring;
+use crate::Scorer;
 
 /// A (possible partial) match of query within the target string. Matched chars
 /// are stored as indices into the target string.
@@ -11,10 +11,41 @@ use crate::Scoring;
 pub struct Match {
     /// Accumulative score
     score: isize,
-    /// Count of current consecutive matched chars
+    /// Count of consecutive matched chars before the last matched char
     consecutive: usize,
     /// Matched char indices
     matched: Vec<usize>,
+    /// Byte ranges of the matched chars in the target string
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    bytes: Vec<Range<usize>>,
+    /// Count of chars in the target string
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    target_len: usize,
+    /// Score of a perfect match of the query
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    best_score: isize,
+    /// Typos in the query, ordered by query index
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    typos: Vec<Typo>,
+}
+
+/// A typo in the query of a match, see [`FuzzySearch::max_typos`](crate::FuzzySearch::max_typos).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum Typo {
+    /// The query char at this index was not matched
+    Missing(usize),
+    /// The query chars at this index and the next one were matched in reverse order
+    Swapped(usize),
+}
+
+impl Typo {
+    /// Returns the index of the (first) query char of this typo.
+    pub fn query_idx(&self) -> usize {
+        match *self {
+            Typo::Missing(idx) | Typo::Swapped(idx) => idx,
+        }
+    }
 }
 
 impl Match {
@@ -24,53 +55,328 @@ impl Match {
             score,
             consecutive,
             matched,
+            bytes: Vec::new(),
+            target_len: 0,
+            best_score: 0,
+            typos: Vec::new(),
         }
     }
 
+    /// Adds `typos` to this match and `score` (the penalty for the typos) to its score.
+    pub(crate) fn add_typos<I: IntoIterator<Item = Typo>>(&mut self, typos: I, score: isize) {
+        self.typos.extend(typos);
+        self.typos.sort_by_key(Typo::query_idx);
+        self.score += score;
+    }
+
+    /// Sets the score of a perfect match of the query, see [`Match::quality`].
+    pub(crate) fn set_best_score(&mut self, best_score: isize) {
+        self.best_score = best_score;
+    }
+
+    /// Replaces the matched indices with the matched char indices of the target string and
+    /// their byte ranges, and sets the char count of the target string.
+    pub(crate) fn set_positions(
+        &mut self,
+        matched: Vec<usize>,
+        bytes: Vec<Range<usize>>,
+        target_len: usize,
+    ) {
+        debug_assert_eq!(matched.len(), bytes.len());
+
+        self.matched = matched;
+        self.bytes = bytes;
+        self.target_len = target_len;
+    }
+
     /// Returns the accumulative score for this match.
     pub fn score(&self) -> isize {
         self.score
     }
 
+    /// Returns the score of a perfect match of the query, where every char is a consecutive
+    /// word start matching case (and accents, and path segments for path aware searches).
+    pub fn best_score(&self) -> isize {
+        self.best_score
+    }
+
+    /// Returns the score of this match relative to [`Match::best_score`], in the range
+    /// `0.0..=1.0`.
+    ///
+    /// Unlike the score, the quality does not grow with the length of the query, so the same
+    /// threshold can be used for all queries. Returns `1.0` if the best score is not positive.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::best_match;
+    ///
+    /// assert_eq!(best_match("t", "two").unwrap().quality(), 1.0);
+    /// assert!(best_match("tt", "the_two").unwrap().quality() > 0.8);
+    /// assert!(best_match("tt", "tetra").unwrap().quality() < 0.5);
+    /// ```
+    pub fn quality(&self) -> f64 {
+        if self.best_score <= 0 {
+            return 1.0;
+        }
+
+        (self.score as f64 / self.best_score as f64).clamp(0.0, 1.0)
+    }
+
+    /// Returns the count of chars in the target string this match was found in.
+    pub fn target_len(&self) -> usize {
+        self.target_len
+    }
+
+    /// Returns an iterator over the typos in the query, ordered by query index.
+    ///
+    /// Only matches of searches with [`FuzzySearch::max_typos`](crate::FuzzySearch::max_typos)
+    /// can have typos.
+    pub fn typos(&self) -> Iter<'_, Typo> {
+        self.typos.iter()
+    }
+
+    /// Returns an iterator over the indices of the query chars that were not matched.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::FuzzySearch;
+    ///
+    /// let m = FuzzySearch::new("scxc", "SoccerCartoonController")
+    ///     .max_typos(1)
+    ///     .best_match()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(m.unmatched_query_indices().collect::<Vec<usize>>(), vec![2]);
+    /// ```
+    pub fn unmatched_query_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.typos.iter().filter_map(|typo| match *typo {
+            Typo::Missing(idx) => Some(idx),
+            Typo::Swapped(_) => None,
+        })
+    }
+
+    /// Returns the index of the query char matched by every matched char, in order of the
+    /// matched chars.
+    pub(crate) fn query_indices(&self, query_len: usize) -> Vec<usize> {
+        let mut indices = Vec::with_capacity(query_len);
+        let mut typos = self.typos.iter().peekable();
+        let mut idx = 0;
+
+        while idx < query_len {
+            match typos.peek() {
+                Some(Typo::Missing(typo_idx)) if *typo_idx == idx => {
+                    idx += 1;
+                }
+                Some(Typo::Swapped(typo_idx)) if *typo_idx == idx => {
+                    indices.extend(&[idx + 1, idx]);
+                    idx += 2;
+                }
+                _ => {
+                    indices.push(idx);
+                    idx += 1;
+
+                    continue;
+                }
+            }
+
+            typos.next();
+        }
+
+        indices
+    }
+
     /// Returns an iterator over the matched char indices.
-    pub fn matched_indices(&self) -> Iter<usize> {
+    pub fn matched_indices(&self) -> Iter<'_, usize> {
         self.matched.iter()
     }
 
+    /// Returns an iterator over the byte ranges of the matched chars in the target string.
+    ///
+    /// Yields one range per matched char index, so `&target[range]` is the matched char.
+    /// Matches deserialized from data without byte ranges (written by older versions) have
+    /// none, methods taking the target string compute them from the matched char indices.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::best_match;
+    ///
+    /// let target = "Fööbär";
+    /// let m = best_match("fb", target).unwrap();
+    ///
+    /// let matched = m.matched_byte_ranges().map(|r| &target[r.clone()]).collect::<String>();
+    ///
+    /// assert_eq!(matched, "Fb");
+    /// ```
+    pub fn matched_byte_ranges(&self) -> Iter<'_, Range<usize>> {
+        self.bytes.iter()
+    }
+
+    /// Returns the ranges of the matched chars in UTF-16 code units of `target`, one
+    /// range per matched char index. Useful for editors and protocols that
+    /// address text in UTF-16, like the Language Server Protocol.
+    ///
+    /// `target` must be the string this match was found in.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::best_match;
+    ///
+    /// let target = "🦀 crab";
+    /// let m = best_match("cb", target).unwrap();
+    ///
+    /// assert_eq!(m.matched_utf16_ranges(target), vec![3..4, 6..7]);
+    /// ```
+    pub fn matched_utf16_ranges(&self, target: &str) -> Vec<Range<usize>> {
+        let mut offsets = Utf16Offsets::new(target);
+
+        self.byte_ranges(target)
+            .iter()
+            .map(|r| offsets.range(r))
+            .collect()
+    }
+
+    /// Returns the ranges of [`Match::continuous_matches`] in UTF-16 code units of `target`.
+    ///
+    /// `target` must be the string this match was found in.
+    pub fn continuous_utf16_ranges(&self, target: &str) -> Vec<Range<usize>> {
+        let mut offsets = Utf16Offsets::new(target);
+        let bytes = self.byte_ranges(target);
+
+        self.continuous_matches_with(&bytes)
+            .map(|c| offsets.range(&c.byte_range()))
+            .collect()
+    }
+
     /// Returns an iterator that groups the individual char matches into groups.
-    pub fn continuous_matches(&self) -> ContinuousMatches {
+    ///
+    /// The byte ranges of the groups are empty for matches without byte ranges, see
+    /// [`Match::matched_byte_ranges`].
+    pub fn continuous_matches(&self) -> ContinuousMatches<'_> {
+        self.continuous_matches_with(&self.bytes)
+    }
+
+    /// Like [`Match::continuous_matches`], with the byte ranges of the matched chars in `bytes`.
+    pub(crate) fn continuous_matches_with<'a>(
+        &'a self,
+        bytes: &'a [Range<usize>],
+    ) -> ContinuousMatches<'a> {
         ContinuousMatches {
             matched: &self.matched,
+            bytes,
             current: 0,
         }
     }
 
+    /// Returns the byte ranges of the matched chars in `target`, the string this match was
+    /// found in. Computed from the matched char indices if this match has none.
+    pub(crate) fn byte_ranges(&self, target: &str) -> Cow<'_, [Range<usize>]> {
+        if self.bytes.len() == self.matched.len() {
+            return Cow::Borrowed(&self.bytes);
+        }
+
+        let chars = target
+            .char_indices()
+            .map(|(i, c)| i..i + c.len_utf8())
+            .collect::<Vec<Range<usize>>>();
+
+        Cow::Owned(
+            self.matched
+                .iter()
+                .filter_map(|&i| chars.get(i).cloned())
+                .collect(),
+        )
+    }
+
+    /// Returns `true` if `other` is the same match, that is it has the same score and
+    /// matched the same chars of a target string of the same length.
+    ///
+    /// `==` only compares the scores of matches, so different alignments with equal scores
+    /// are equal.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::best_match;
+    ///
+    /// let a = best_match("a", "a_b").unwrap();
+    /// let b = best_match("a", "a_c").unwrap();
+    /// let c = best_match("a", "b_a").unwrap();
+    ///
+    /// assert!(a.structural_eq(&b));
+    /// assert!(a == c);
+    /// assert!(!a.structural_eq(&c));
+    /// ```
+    pub fn structural_eq(&self, other: &Match) -> bool {
+        self.score == other.score
+            && self.consecutive == other.consecutive
+            && self.matched == other.matched
+            && self.bytes == other.bytes
+            && self.target_len == other.target_len
+            && self.typos == other.typos
+    }
+
+    /// Returns the score of this match extended with `other`, see [`Match::extend_with`].
+    pub(crate) fn extended_score<S: Scorer + ?Sized>(&self, other: &Match, scorer: &S) -> isize {
+        self.extended(other, scorer).1
+    }
+
     /// Extends this match with `other`.
-    pub fn extend_with(&mut self, other: &Match, scoring: &Scoring) {
-        self.score += other.score;
-        self.consecutive += other.consecutive;
+    pub fn extend_with<S: Scorer + ?Sized>(&mut self, other: &Match, scorer: &S) {
+        let (consecutive, score) = self.extended(other, scorer);
+
+        self.consecutive = consecutive;
+        self.score = score;
+
+        self.matched.extend(&other.matched);
+        self.bytes.extend(other.bytes.iter().cloned());
+        self.typos.extend(&other.typos);
+    }
+
+    /// Returns the count of consecutive chars and the score of this match extended with `other`.
+    ///
+    /// The consecutive bonus of the first char of `other` is already part of its score, only
+    /// the gap between the matches is scored.
+    fn extended<S: Scorer + ?Sized>(&self, other: &Match, scorer: &S) -> (usize, isize) {
+        let mut score = self.score + other.score;
 
         if let (Some(last), Some(first)) = (self.matched.last(), other.matched.first()) {
             let distance = first - last;
 
-            match distance {
-                0 => {}
-                1 => {
-                    self.consecutive += 1;
-                    self.score += self.consecutive as isize * scoring.bonus_consecutive;
-                }
-                _ => {
-                    self.consecutive = 0;
-                    let penalty = (distance as isize - 1) * scoring.penalty_distance;
-                    self.score -= penalty;
-                }
+            if distance > 1 {
+                score += scorer.score_gap(distance - 1);
             }
         }
 
-        self.matched.extend(&other.matched);
+        let consecutive = if other.matched.is_empty() {
+            self.consecutive
+        } else {
+            other.consecutive
+        };
+
+        (consecutive, score)
+    }
+
+    /// Returns the count of consecutive chars at the end of this match.
+    pub(crate) fn consecutive(&self) -> usize {
+        self.consecutive
     }
 }
 
+/// Matches are ordered by score only, see [`Ranking`](crate::Ranking) for ordering by other keys.
 impl Ord for Match {
     fn cmp(&self, other: &Match) -> Ordering {
         self.score.cmp(&other.score)
@@ -85,6 +391,7 @@ impl PartialOrd for Match {
 
 impl Eq for Match {}
 
+/// Compares the scores of matches, see [`Match::structural_eq`] for comparing alignments.
 impl PartialEq for Match {
     fn eq(&self, other: &Match) -> bool {
         self.score == other.score
@@ -96,11 +403,17 @@ impl PartialEq for Match {
 pub struct ContinuousMatch {
     start: usize,
     len: usize,
+    bytes: Range<usize>,
 }
 
 impl ContinuousMatch {
+    #[cfg(test)]
     pub(crate) fn new(start: usize, len: usize) -> Self {
-        ContinuousMatch { start, len }
+        ContinuousMatch::with_bytes(start, len, 0..0)
+    }
+
+    pub(crate) fn with_bytes(start: usize, len: usize, bytes: Range<usize>) -> Self {
+        ContinuousMatch { start, len, bytes }
     }
 
     /// Returns the start index of this group.
@@ -112,6 +425,18 @@ impl ContinuousMatch {
     pub fn len(&self) -> usize {
         self.len
     }
+
+    /// Returns `true` if this group does not contain any chars.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the byte range of this group in the target string, so `&target[range]`
+    /// is the matched text. Empty for matches without byte ranges, see
+    /// [`Match::matched_byte_ranges`].
+    pub fn byte_range(&self) -> Range<usize> {
+        self.bytes.clone()
+    }
 }
 
 impl Eq for ContinuousMatch {}
@@ -125,23 +450,34 @@ impl PartialEq for ContinuousMatch {
 /// Iterator returning [`ContinuousMatch`]es from the matched char indices in a [`Match`]
 pub struct ContinuousMatches<'a> {
     matched: &'a Vec<usize>,
+    bytes: &'a [Range<usize>],
     current: usize,
 }
 
-impl<'a> Iterator for ContinuousMatches<'_> {
+impl ContinuousMatches<'_> {
+    /// Builds the group of `len` matched chars starting at matched position `first`.
+    fn group(&self, first: usize, len: usize) -> ContinuousMatch {
+        let bytes = match (self.bytes.get(first), self.bytes.get(first + len - 1)) {
+            (Some(start), Some(end)) => start.start..end.end,
+            _ => 0..0,
+        };
+
+        ContinuousMatch::with_bytes(self.matched[first], len, bytes)
+    }
+}
+
+impl Iterator for ContinuousMatches<'_> {
     type Item = ContinuousMatch;
 
     fn next(&mut self) -> Option<ContinuousMatch> {
-        let mut start = None;
+        let first = self.current;
         let mut len = 0;
 
         let mut last_idx = None;
 
         for idx in self.matched.iter().cloned().skip(self.current) {
-            start = start.or(Some(idx));
-
             if last_idx.is_some() && (idx - last_idx.unwrap() != 1) {
-                return Some(ContinuousMatch::new(start.unwrap(), len));
+                return Some(self.group(first, len));
             }
 
             self.current += 1;
@@ -150,18 +486,52 @@ impl<'a> Iterator for ContinuousMatches<'_> {
         }
 
         if last_idx.is_some() {
-            return Some(ContinuousMatch::new(start.unwrap(), len));
+            return Some(self.group(first, len));
         }
 
         None
     }
 }
 
+/// Converts increasing byte offsets of a string into UTF-16 offsets in a single pass.
+struct Utf16Offsets<'a> {
+    target: &'a str,
+    byte_idx: usize,
+    utf16_idx: usize,
+}
+
+impl<'a> Utf16Offsets<'a> {
+    fn new(target: &'a str) -> Self {
+        Utf16Offsets {
+            target,
+            byte_idx: 0,
+            utf16_idx: 0,
+        }
+    }
+
+    /// Returns the UTF-16 offset of `byte_idx`, which must not be lower than the previous one.
+    fn offset(&mut self, byte_idx: usize) -> usize {
+        self.utf16_idx += self.target[self.byte_idx..byte_idx]
+            .chars()
+            .map(char::len_utf16)
+            .sum::<usize>();
+        self.byte_idx = byte_idx;
+
+        self.utf16_idx
+    }
+
+    fn range(&mut self, bytes: &Range<usize>) -> Range<usize> {
+        self.offset(bytes.start)..self.offset(bytes.end)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::ops::Range;
+
     use crate::Scoring;
 
-    use super::{ContinuousMatch, Match};
+    use super::{ContinuousMatch, Match, Utf16Offsets};
 
     #[test]
     fn continuous() {
@@ -170,9 +540,9 @@ mod tests {
         assert_eq!(
             m.continuous_matches().collect::<Vec<ContinuousMatch>>(),
             vec![
-                ContinuousMatch { start: 0, len: 3 },
-                ContinuousMatch { start: 5, len: 2 },
-                ContinuousMatch { start: 10, len: 1 },
+                ContinuousMatch::new(0, 3),
+                ContinuousMatch::new(5, 2),
+                ContinuousMatch::new(10, 1),
             ]
         )
     }
@@ -187,7 +557,7 @@ mod tests {
         a.extend_with(&b, &s);
 
         assert_eq!(a.score(), 24 - s.penalty_distance);
-        assert_eq!(a.consecutive, 0);
+        assert_eq!(a.consecutive, 3);
         assert_eq!(a.matched_indices().len(), 6);
     }
 
@@ -200,8 +570,32 @@ mod tests {
 
         a.extend_with(&b, &s);
 
-        assert_eq!(a.score(), 16 + 8 + (3 + 3 + 1) * s.bonus_consecutive);
-        assert_eq!(a.consecutive, 3 + 3 + 1);
+        assert_eq!(a.score(), 16 + 8);
+        assert_eq!(a.consecutive, 3);
         assert_eq!(a.matched_indices().len(), 6);
     }
+
+    #[test]
+    fn continuous_byte_ranges() {
+        let mut m = Match::with_matched(0, 0, vec![0, 1, 2]);
+        m.set_positions(vec![0, 1, 3], vec![0..1, 1..3, 4..8], 4);
+
+        assert_eq!(
+            m.continuous_matches()
+                .map(|c| c.byte_range())
+                .collect::<Vec<Range<usize>>>(),
+            vec![0..3, 4..8]
+        );
+    }
+
+    #[test]
+    fn utf16_offsets() {
+        let s = "a🦀bé👀";
+        let mut offsets = Utf16Offsets::new(s);
+
+        assert_eq!(offsets.range(&(0..1)), 0..1);
+        assert_eq!(offsets.range(&(1..5)), 1..3);
+        assert_eq!(offsets.range(&(6..8)), 4..5);
+        assert_eq!(offsets.range(&(8..12)), 5..7);
+    }
 }
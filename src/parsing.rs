@@ -1,14 +1,56 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{hash_map::DefaultHasher, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::iter::FromIterator;
 
+use crate::boundary::{WordBoundary, WordStart};
+
+#[cfg(feature = "unicode-segmentation")]
+use unicode_segmentation::UnicodeSegmentation;
+
 pub type CharSet = HashSet<char>;
 pub type Occurrences = HashMap<char, Vec<Occurrence>>;
 
+/// Options for splitting query and target strings into matchable units.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    /// Use extended grapheme clusters instead of chars as units
+    pub graphemes: bool,
+    /// Strip accents (combining marks) from chars, e.g. match `é` as `e`
+    pub fold_accents: bool,
+    /// Treat the target as a file path, see [`TargetChar::is_segment_start`]
+    pub paths: bool,
+    /// Rules for finding word starts, see [`TargetChar::is_start`]
+    pub word_boundary: WordBoundary,
+}
+
+impl ParseOptions {
+    /// Returns a hash of these options, so prepared targets can be checked against the options
+    /// of a pattern without storing and comparing all of them.
+    pub fn id(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        self.hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Occurrence {
     pub target_idx: usize,
     pub is_start: bool,
+    /// See [`TargetChar::start_kind`]
+    pub start_kind: Option<WordStart>,
+    /// See [`TargetChar::is_segment_start`]
+    pub is_segment_start: bool,
+    /// See [`TargetChar::in_basename`]
+    pub in_basename: bool,
+    /// See [`TargetChar::folded`]
     pub char: char,
+    /// See [`TargetChar::accent`]
+    pub accent: Option<char>,
+    /// See [`QueryChar::tail`]
+    pub tail: Option<String>,
 }
 
 impl Eq for Occurrence {}
@@ -18,82 +60,355 @@ impl PartialEq for Occurrence {
         self.target_idx == other.target_idx
             && self.char == other.char
             && self.is_start == other.is_start
+            && self.start_kind == other.start_kind
+            && self.is_segment_start == other.is_segment_start
+            && self.in_basename == other.in_basename
+            && self.accent == other.accent
+            && self.tail == other.tail
+    }
+}
+
+pub type TargetChars = Vec<TargetChar>;
+
+/// A unit of the target string. Usually a single char, but can be a whole grapheme
+/// cluster (see [`ParseOptions::graphemes`]).
+///
+/// Units only store what is needed for matching, their chars are read from the target
+/// string (see [`unit_str`]). Targets are usually prepared up front in large numbers, so
+/// this is kept small.
+#[derive(Clone, Debug)]
+pub struct TargetChar {
+    /// First char of the unit with accents folded (see [`ParseOptions::fold_accents`])
+    pub folded: char,
+    /// `folded` with its case folded (see [`fold_case`])
+    pub lower: char,
+    /// First char of the unit in lowercase if folding removed an accent from it
+    pub accent: Option<char>,
+    /// Why this starts a word (see [`WordBoundary`]), [`None`] if it doesn't
+    pub start_kind: Option<WordStart>,
+    /// Follows a path separator (only set for paths, see [`ParseOptions::paths`])
+    pub is_segment_start: bool,
+    /// Part of the last path segment (only set for paths, see [`ParseOptions::paths`])
+    pub in_basename: bool,
+    /// Index of the first char of the unit in the chars of the target string
+    pub char_idx: usize,
+    /// Byte offset of the unit in the target string
+    pub byte_idx: usize,
+}
+
+impl TargetChar {
+    fn new(
+        original: char,
+        lower: char,
+        start_kind: Option<WordStart>,
+        char_idx: usize,
+        byte_idx: usize,
+        options: &ParseOptions,
+    ) -> Self {
+        let (folded, folded_lower) = if options.fold_accents {
+            (fold_accent(original), fold_accent(lower))
+        } else {
+            (original, lower)
+        };
+
+        TargetChar {
+            folded,
+            lower: folded_lower,
+            accent: if folded != original {
+                Some(lower)
+            } else {
+                None
+            },
+            start_kind,
+            is_segment_start: false,
+            in_basename: false,
+            char_idx,
+            byte_idx,
+        }
+    }
+
+    /// Starts a word, see [`WordBoundary`].
+    pub fn is_start(&self) -> bool {
+        self.start_kind.is_some()
+    }
+
+    /// Returns the first char of the unit in `string`, the target string it was processed from.
+    pub fn original(&self, string: &str) -> char {
+        string[self.byte_idx..].chars().next().unwrap_or_default()
+    }
+}
+
+impl Eq for TargetChar {}
+
+impl PartialEq for TargetChar {
+    fn eq(&self, other: &TargetChar) -> bool {
+        self.folded == other.folded
+            && self.lower == other.lower
+            && self.accent == other.accent
+            && self.start_kind == other.start_kind
+            && self.is_segment_start == other.is_segment_start
+            && self.in_basename == other.in_basename
+            && self.char_idx == other.char_idx
+            && self.byte_idx == other.byte_idx
+    }
+}
+
+/// Returns the chars of unit `i` of `units`, which were processed from `string`.
+///
+/// Units cover the target string without gaps from the first unit on (chars that are not
+/// matched on their own belong to the unit before them), so a unit ends where the next one
+/// starts.
+pub fn unit_str<'s>(string: &'s str, units: &[TargetChar], i: usize) -> &'s str {
+    let end = units.get(i + 1).map_or(string.len(), |next| next.byte_idx);
+
+    &string[units[i].byte_idx..end]
+}
+
+/// Returns the chars following the first char of `unit` which are used for matching it,
+/// without accents if they are folded.
+fn matched_tail(unit: &str, options: &ParseOptions) -> Option<String> {
+    let mut chars = unit.chars();
+
+    chars.next();
+
+    Some(
+        chars
+            .filter(|&c| !options.fold_accents || !is_accent(c))
+            .collect::<String>(),
+    )
+    .filter(|t| !t.is_empty())
+}
+
+/// Maps `c` to a single char that is equal for all case variants of `c` (simple case
+/// folding), e.g. `Σ`, `σ` and `ς` all map to `σ`.
+///
+/// Lowercasing whole strings can't be used for this because some chars lowercase to multiple
+/// chars (`İ` -> `i̇`), which would shift the chars following them. Chars that only fold to
+/// multiple chars (`ß` -> `ss`, `ﬁ` -> `fi`) are lowercased and otherwise kept as they are.
+fn fold_case(c: char) -> char {
+    fn single(mut chars: impl Iterator<Item = char>) -> Option<char> {
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    }
+
+    single(c.to_uppercase())
+        .and_then(|upper| single(upper.to_lowercase()))
+        .or_else(|| single(c.to_lowercase()))
+        // `İ` is the only char lowercasing to multiple chars, an `i` followed by a combining dot
+        .or_else(|| c.to_lowercase().next())
+        .unwrap_or(c)
+}
+
+/// Returns `true` for combining marks, which are dropped when folding accents.
+fn is_accent(c: char) -> bool {
+    #[cfg(feature = "unicode-normalization")]
+    {
+        unicode_normalization::char::is_combining_mark(c)
+    }
+    #[cfg(not(feature = "unicode-normalization"))]
+    {
+        let _ = c;
+
+        false
+    }
+}
+
+/// Strips accents from `c` if its canonical decomposition is a base char followed
+/// by combining marks only (`é` -> `e`). Other chars are returned unchanged.
+fn fold_accent(c: char) -> char {
+    #[cfg(feature = "unicode-normalization")]
+    {
+        let mut base = None;
+        let mut only_accents = true;
+
+        unicode_normalization::char::decompose_canonical(c, |d| {
+            if base.is_none() {
+                base = Some(d);
+            } else if !is_accent(d) {
+                only_accents = false;
+            }
+        });
+
+        match base {
+            Some(b) if only_accents && !is_accent(b) => b,
+            _ => c,
+        }
+    }
+    #[cfg(not(feature = "unicode-normalization"))]
+    {
+        c
     }
 }
 
-pub fn build_occurrences(query: &QueryChars, string: &str, case_insensitive: bool) -> Occurrences {
-    let query_chars = condense(query, case_insensitive);
+/// Lowercases and classifies the units of `string` into `chars`.
+///
+/// Previous contents of `chars` are cleared but its allocation is kept.
+pub fn process_target(string: &str, options: &ParseOptions, chars: &mut TargetChars) {
+    chars.clear();
 
-    let mut occurrences = HashMap::new();
+    #[cfg(feature = "unicode-segmentation")]
+    let mut clusters = if options.graphemes {
+        Some(string.grapheme_indices(true).map(|(i, _)| i).peekable())
+    } else {
+        None
+    };
+    #[cfg(feature = "unicode-segmentation")]
+    let mut words = if options.word_boundary.unicode_words {
+        Some(string.split_word_bound_indices().map(|(i, _)| i).peekable())
+    } else {
+        None
+    };
 
-    let lower = string.to_lowercase();
+    let boundary = &options.word_boundary;
 
     let mut prev_is_upper = false;
+    let mut prev_is_digit = false;
     let mut prev_is_sep = true;
-    let mut prev_is_start = false;
+    // Count of uppercase chars directly preceding the current one
+    let mut upper_run = 0;
 
-    for (i, (lower_c, original_c)) in lower.chars().zip(string.chars()).enumerate() {
-        let mut is_start = false;
-        let is_sep = is_word_sep(original_c);
-        let is_upper = original_c.is_uppercase();
+    for (char_idx, (byte_idx, original_c)) in string.char_indices().enumerate() {
+        #[cfg(feature = "unicode-segmentation")]
+        let starts_unicode_word = words
+            .as_mut()
+            .is_some_and(|words| words.next_if_eq(&byte_idx).is_some());
+        #[cfg(not(feature = "unicode-segmentation"))]
+        let starts_unicode_word = false;
 
-        let key_char = if case_insensitive {
-            lower_c
-        } else {
-            original_c
-        };
+        #[cfg(feature = "unicode-segmentation")]
+        {
+            if let Some(ref mut clusters) = clusters {
+                if clusters.next_if_eq(&byte_idx).is_none() {
+                    continue;
+                }
+            }
+        }
+
+        // Accents without a base char can't be matched, they stay with the previous unit so
+        // highlighting doesn't split them from their base char
+        if options.fold_accents && is_accent(original_c) {
+            continue;
+        }
+
+        let lower_c = fold_case(original_c);
+        let is_sep = boundary.is_separator(original_c);
+        let is_upper = original_c.is_uppercase();
+        let is_digit = original_c.is_numeric();
 
         if is_sep {
             prev_is_upper = false;
+            prev_is_digit = false;
             prev_is_sep = true;
-            prev_is_start = false;
-
-            if query_chars.contains(&key_char) {
-                occurrences
-                    .entry(key_char)
-                    .or_insert(Vec::new())
-                    .push(Occurrence {
-                        char: original_c,
-                        target_idx: i,
-                        is_start,
-                    });
-            }
+            upper_run = 0;
+
+            chars.push(TargetChar::new(
+                original_c, lower_c, None, char_idx, byte_idx, options,
+            ));
 
             continue;
         }
 
-        if prev_is_sep {
-            is_start = true;
+        let start_kind = if prev_is_sep {
+            Some(chars.last().map_or(WordStart::String, |sep| {
+                WordStart::after_separator(sep.original(string))
+            }))
+        } else if (starts_unicode_word && original_c.is_alphanumeric())
+            || (boundary.letter_digit && prev_is_digit != is_digit)
+        {
+            Some(WordStart::Other)
+        } else if boundary.case_change && is_upper && !prev_is_upper {
+            Some(WordStart::CaseChange)
         } else {
-            if !prev_is_start && (prev_is_upper != is_upper) {
-                is_start = true;
+            None
+        };
+
+        // The last char of an uppercase run followed by a lowercase char starts a word (`HTML|Parser`)
+        if boundary.case_change && upper_run > 1 && original_c.is_lowercase() {
+            if let Some(prev) = chars.last_mut() {
+                prev.start_kind.get_or_insert(WordStart::CaseChange);
             }
         }
 
-        if query_chars.contains(&key_char) {
-            occurrences
-                .entry(key_char)
-                .or_insert(Vec::new())
-                .push(Occurrence {
-                    char: original_c,
-                    target_idx: i,
-                    is_start,
-                });
-        }
+        chars.push(TargetChar::new(
+            original_c, lower_c, start_kind, char_idx, byte_idx, options,
+        ));
 
-        prev_is_start = is_start;
         prev_is_sep = is_sep;
         prev_is_upper = is_upper;
+        prev_is_digit = is_digit;
+        upper_run = if is_upper { upper_run + 1 } else { 0 };
     }
 
-    occurrences
+    if options.paths {
+        mark_path_segments(string, chars);
+    }
+}
+
+fn is_path_sep(c: char) -> bool {
+    c == '/' || c == '\\'
 }
 
-fn is_word_sep(c: char) -> bool {
-    !c.is_alphanumeric()
+/// Marks the units following a path separator and the units of the last path segment
+/// (ignoring trailing separators, so for `src/bin/` the last segment is `bin`).
+fn mark_path_segments(string: &str, chars: &mut [TargetChar]) {
+    let end = chars
+        .iter()
+        .rposition(|c| !is_path_sep(c.original(string)))
+        .map_or(0, |i| i + 1);
+    let basename_start = chars[..end]
+        .iter()
+        .rposition(|c| is_path_sep(c.original(string)))
+        .map_or(0, |i| i + 1);
+
+    let mut prev_is_sep = false;
+
+    for (i, c) in chars.iter_mut().enumerate() {
+        let is_sep = is_path_sep(c.original(string));
+
+        c.is_segment_start = prev_is_sep && !is_sep;
+        c.in_basename = i >= basename_start && i < end;
+
+        prev_is_sep = is_sep;
+    }
 }
 
-fn condense(s: &QueryChars, case_insensitive: bool) -> CharSet {
+/// Collects the occurrences of `query_chars` in `target`, the units of `string`, into
+/// `occurrences`.
+///
+/// Previously collected occurrences are cleared but their allocations are kept.
+pub fn build_occurrences(
+    query_chars: &CharSet,
+    string: &str,
+    target: &[TargetChar],
+    case_insensitive: bool,
+    options: &ParseOptions,
+    occurrences: &mut Occurrences,
+) {
+    for occs in occurrences.values_mut() {
+        occs.clear();
+    }
+
+    for (i, c) in target.iter().enumerate() {
+        let key_char = if case_insensitive { c.lower } else { c.folded };
+
+        if query_chars.contains(&key_char) {
+            occurrences.entry(key_char).or_default().push(Occurrence {
+                char: c.folded,
+                target_idx: i,
+                is_start: c.is_start(),
+                start_kind: c.start_kind,
+                is_segment_start: c.is_segment_start,
+                in_basename: c.in_basename,
+                accent: c.accent,
+                tail: matched_tail(unit_str(string, target, i), options),
+            });
+        }
+    }
+}
+
+pub fn condense(s: &QueryChars, case_insensitive: bool) -> CharSet {
     HashSet::from_iter(s.iter().map(|qc| {
         if case_insensitive {
             qc.lower
@@ -105,32 +420,48 @@ fn condense(s: &QueryChars, case_insensitive: bool) -> CharSet {
 
 pub type QueryChars = Vec<QueryChar>;
 
+/// A unit of the query, see [`TargetChar`].
 #[derive(Clone, Debug)]
 pub struct QueryChar {
+    /// Char in original case, with accents folded (see [`TargetChar::folded`])
     pub original: char,
     pub lower: char,
+    /// See [`TargetChar::accent`]
+    pub accent: Option<char>,
+    /// Chars following `original` in the same unit, with accents folded
+    pub tail: Option<String>,
 }
 
 impl Eq for QueryChar {}
 
 impl PartialEq for QueryChar {
     fn eq(&self, other: &QueryChar) -> bool {
-        self.original == other.original && self.lower == other.lower
+        self.original == other.original
+            && self.lower == other.lower
+            && self.accent == other.accent
+            && self.tail == other.tail
     }
 }
 
-pub fn process_query(query: &str) -> QueryChars {
-    let lower_query = query.to_lowercase();
+pub fn process_query(query: &str, options: &ParseOptions) -> QueryChars {
+    let mut chars = TargetChars::new();
+
+    process_target(query, options, &mut chars);
 
-    query
-        .chars()
-        .zip(lower_query.chars())
-        .filter_map(|(original, lower)| {
-            if original.is_whitespace() {
+    chars
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| {
+            if c.original(query).is_whitespace() {
                 return None;
             }
 
-            Some(QueryChar { original, lower })
+            Some(QueryChar {
+                original: c.folded,
+                lower: c.lower,
+                accent: c.accent,
+                tail: matched_tail(unit_str(query, &chars, i), options),
+            })
         })
         .collect::<Vec<QueryChar>>()
 }
@@ -140,7 +471,289 @@ mod tests {
     use std::collections::HashSet;
     use std::iter::FromIterator;
 
-    use super::{build_occurrences, condense, is_word_sep, process_query, Occurrence, QueryChar};
+    use super::{
+        build_occurrences, condense, fold_case, process_query, process_target, unit_str,
+        Occurrence, Occurrences, ParseOptions, QueryChar, TargetChar, TargetChars, WordBoundary,
+        WordStart,
+    };
+
+    fn occurrences_of(query: &str, target: &str) -> Occurrences {
+        let mut chars = TargetChars::new();
+        let mut occs = Occurrences::new();
+
+        process_target(target, &ParseOptions::default(), &mut chars);
+        build_occurrences(
+            &condense(&process_query(query, &ParseOptions::default()), true),
+            target,
+            &chars,
+            true,
+            &ParseOptions::default(),
+            &mut occs,
+        );
+
+        occs
+    }
+
+    fn word_starts(target: &str) -> Vec<usize> {
+        word_starts_with(target, WordBoundary::default())
+    }
+
+    fn word_starts_with(target: &str, word_boundary: WordBoundary) -> Vec<usize> {
+        let mut chars = TargetChars::new();
+        let options = ParseOptions {
+            word_boundary,
+            ..ParseOptions::default()
+        };
+
+        process_target(target, &options, &mut chars);
+
+        chars
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_start())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-segmentation")]
+    fn grapheme_units() {
+        let mut chars = TargetChars::new();
+        let s = "e\u{301}x👩‍🔬";
+
+        process_target(
+            s,
+            &ParseOptions {
+                graphemes: true,
+                ..ParseOptions::default()
+            },
+            &mut chars,
+        );
+
+        assert_eq!(
+            chars
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (unit_str(s, &chars, i), c.char_idx, c.byte_idx))
+                .collect::<Vec<_>>(),
+            vec![("e\u{301}", 0, 0), ("x", 2, 3), ("👩‍🔬", 3, 4)]
+        );
+    }
+
+    #[test]
+    fn compact_units() {
+        assert!(std::mem::size_of::<TargetChar>() <= 32);
+
+        let mut chars = TargetChars::new();
+        let s = "Cafe\u{301}s";
+        let options = ParseOptions {
+            fold_accents: true,
+            ..ParseOptions::default()
+        };
+
+        process_target(s, &options, &mut chars);
+
+        let units = (0..chars.len())
+            .map(|i| unit_str(s, &chars, i))
+            .collect::<Vec<_>>();
+
+        if cfg!(feature = "unicode-normalization") {
+            assert_eq!(units, vec!["C", "a", "f", "e\u{301}", "s"]);
+        } else {
+            assert_eq!(units, vec!["C", "a", "f", "e", "\u{301}", "s"]);
+        }
+
+        assert_ne!(options.id(), ParseOptions::default().id());
+        assert_eq!(options.id(), options.clone().id());
+    }
+
+    #[test]
+    fn case_folding() {
+        assert_eq!(fold_case('A'), 'a');
+        assert_eq!(fold_case('İ'), 'i');
+        assert_eq!(fold_case('Σ'), 'σ');
+        assert_eq!(fold_case('ς'), 'σ');
+        assert_eq!(fold_case('ẞ'), 'ß');
+        assert_eq!(fold_case('ß'), 'ß');
+        assert_eq!(fold_case('ﬁ'), 'ﬁ');
+    }
+
+    #[test]
+    fn multi_char_lowercase_aligned() {
+        let mut chars = TargetChars::new();
+
+        process_target("İstanbul", &ParseOptions::default(), &mut chars);
+
+        assert_eq!(
+            chars
+                .iter()
+                .map(|c| (c.original("İstanbul"), c.lower, c.char_idx, c.byte_idx))
+                .collect::<Vec<_>>()[..3],
+            [('İ', 'i', 0, 0), ('s', 's', 1, 2), ('t', 't', 2, 3)]
+        );
+    }
+
+    #[test]
+    fn path_segments() {
+        let mut chars = TargetChars::new();
+        let options = ParseOptions {
+            paths: true,
+            ..ParseOptions::default()
+        };
+
+        let marked = |chars: &TargetChars| {
+            chars
+                .iter()
+                .map(|c| match (c.is_segment_start, c.in_basename) {
+                    (true, true) => 'B',
+                    (false, true) => 'b',
+                    (true, false) => 'S',
+                    (false, false) => '.',
+                })
+                .collect::<String>()
+        };
+
+        process_target("src/bin/main.rs", &options, &mut chars);
+        assert_eq!(marked(&chars), "....S...Bbbbbbb");
+
+        process_target("C:\\dev\\", &options, &mut chars);
+        assert_eq!(marked(&chars), "...Bbb.");
+
+        process_target("main.rs", &options, &mut chars);
+        assert_eq!(marked(&chars), "bbbbbbb");
+
+        process_target("src/main.rs", &ParseOptions::default(), &mut chars);
+        assert_eq!(marked(&chars), "...........");
+    }
+
+    #[test]
+    fn target_word_starts() {
+        assert_eq!(word_starts("SoccerCartoonController"), vec![0, 6, 13]);
+        assert_eq!(word_starts("some_search thing"), vec![0, 5, 12]);
+        assert_eq!(word_starts("__init__"), vec![2]);
+    }
+
+    #[test]
+    fn acronym_and_digit_word_starts() {
+        assert_eq!(word_starts("HTMLParser"), vec![0, 4]);
+        assert_eq!(word_starts("parseHTML"), vec![0, 5]);
+        assert_eq!(word_starts("utf8Decoder"), vec![0, 3, 4]);
+        assert_eq!(word_starts("Vec2D"), vec![0, 3, 4]);
+        assert_eq!(word_starts("SCC"), vec![0]);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-segmentation")]
+    fn unicode_word_starts() {
+        assert_eq!(word_starts("東京タワー"), vec![0]);
+        assert_eq!(
+            word_starts_with("東京タワー", WordBoundary::unicode()),
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            word_starts_with("Tokyo東京", WordBoundary::unicode()),
+            vec![0, 5, 6]
+        );
+        assert_eq!(
+            word_starts_with("foo_bar", WordBoundary::unicode()),
+            vec![0, 4]
+        );
+        assert_eq!(
+            word_starts_with(
+                "don't stop",
+                WordBoundary {
+                    unicode_words: true,
+                    ..WordBoundary::prose()
+                }
+            ),
+            vec![0, 6]
+        );
+
+        let mut chars = TargetChars::new();
+        let s = "crate::Type";
+
+        process_target(
+            s,
+            &ParseOptions {
+                word_boundary: WordBoundary::unicode(),
+                ..ParseOptions::default()
+            },
+            &mut chars,
+        );
+
+        assert_eq!(
+            chars
+                .iter()
+                .filter_map(|c| c.start_kind.map(|kind| (c.original(s), kind)))
+                .collect::<Vec<_>>(),
+            vec![('c', WordStart::String), ('T', WordStart::Member)]
+        );
+    }
+
+    #[test]
+    fn word_start_kinds() {
+        let mut chars = TargetChars::new();
+
+        let s = "a::B/c_d.eF 1";
+
+        process_target(s, &ParseOptions::default(), &mut chars);
+
+        assert_eq!(
+            chars
+                .iter()
+                .filter_map(|c| c.start_kind.map(|kind| (c.original(s), kind)))
+                .collect::<Vec<_>>(),
+            vec![
+                ('a', WordStart::String),
+                ('B', WordStart::Member),
+                ('c', WordStart::PathSeparator),
+                ('d', WordStart::Joiner),
+                ('e', WordStart::Member),
+                ('F', WordStart::CaseChange),
+                ('1', WordStart::Separator),
+            ]
+        );
+    }
+
+    #[test]
+    fn word_boundary_presets() {
+        assert_eq!(word_starts("my(file).rs"), vec![0, 3, 9]);
+        assert_eq!(
+            word_starts_with("my(file).rs", WordBoundary::paths()),
+            vec![0, 9]
+        );
+        assert_eq!(word_starts("iPhone don't"), vec![0, 1, 7, 11]);
+        assert_eq!(
+            word_starts_with("iPhone don't", WordBoundary::prose()),
+            vec![0, 7]
+        );
+        assert_eq!(word_starts("utf8To16"), vec![0, 3, 4, 6]);
+        assert_eq!(
+            word_starts_with(
+                "utf8To16",
+                WordBoundary {
+                    letter_digit: false,
+                    ..WordBoundary::default()
+                }
+            ),
+            vec![0, 4]
+        );
+    }
+
+    #[test]
+    fn unicode_words_without_feature() {
+        let boundary = WordBoundary {
+            unicode_words: true,
+            ..WordBoundary::default()
+        };
+        let expected = if cfg!(feature = "unicode-segmentation") {
+            vec![0, 1]
+        } else {
+            vec![0]
+        };
+
+        assert_eq!(word_starts_with("東京", boundary), expected);
+    }
 
     #[test]
     fn word_seps() {
@@ -148,18 +761,20 @@ mod tests {
             '/', '\\', '|', '_', '-', ' ', '\t', ':', '.', ',', '~', '>', '<',
         ];
 
-        assert!(seps.into_iter().all(|s| is_word_sep(s)));
+        assert!(seps
+            .into_iter()
+            .all(|c| WordBoundary::default().is_separator(c)));
     }
 
     #[test]
     fn condense_casing() {
         assert_eq!(
-            condense(&process_query("SCC"), true),
+            condense(&process_query("SCC", &ParseOptions::default()), true),
             HashSet::from_iter(vec!['s', 'c']),
             "Query chars not lowercased"
         );
         assert_eq!(
-            condense(&process_query("SCC"), false),
+            condense(&process_query("SCC", &ParseOptions::default()), false),
             HashSet::from_iter(vec!['S', 'C']),
             "Query chars not matching original case"
         );
@@ -171,18 +786,24 @@ mod tests {
             vec![
                 QueryChar {
                     lower: 'a',
-                    original: 'a'
+                    original: 'a',
+                    accent: None,
+                    tail: None
                 },
                 QueryChar {
                     lower: 'b',
-                    original: 'b'
+                    original: 'b',
+                    accent: None,
+                    tail: None
                 },
                 QueryChar {
                     lower: 'c',
-                    original: 'c'
+                    original: 'c',
+                    accent: None,
+                    tail: None
                 }
             ],
-            process_query("a b c"),
+            process_query("a b c", &ParseOptions::default()),
             "Whitespace not removed"
         );
 
@@ -190,18 +811,24 @@ mod tests {
             vec![
                 QueryChar {
                     lower: 'a',
-                    original: 'A'
+                    original: 'A',
+                    accent: None,
+                    tail: None
                 },
                 QueryChar {
                     lower: 'b',
-                    original: 'B'
+                    original: 'B',
+                    accent: None,
+                    tail: None
                 },
                 QueryChar {
                     lower: 'c',
-                    original: 'C'
+                    original: 'C',
+                    accent: None,
+                    tail: None
                 }
             ],
-            process_query("ABC")
+            process_query("ABC", &ParseOptions::default())
         );
     }
 
@@ -211,6 +838,11 @@ mod tests {
             char: 'c',
             target_idx: 0,
             is_start: true,
+            start_kind: Some(WordStart::String),
+            is_segment_start: false,
+            in_basename: false,
+            accent: None,
+            tail: None,
         };
 
         assert_eq!(
@@ -218,7 +850,12 @@ mod tests {
             Occurrence {
                 char: 'c',
                 target_idx: 0,
-                is_start: true
+                is_start: true,
+                start_kind: Some(WordStart::String),
+                is_segment_start: false,
+                in_basename: false,
+                accent: None,
+                tail: None,
             }
         );
         assert_ne!(
@@ -226,7 +863,12 @@ mod tests {
             Occurrence {
                 char: 'c',
                 target_idx: 0,
-                is_start: false
+                is_start: false,
+                start_kind: None,
+                is_segment_start: false,
+                in_basename: false,
+                accent: None,
+                tail: None,
             },
             "is_start differs but eq"
         );
@@ -235,7 +877,12 @@ mod tests {
             Occurrence {
                 char: 'c',
                 target_idx: 1,
-                is_start: true
+                is_start: true,
+                start_kind: Some(WordStart::CaseChange),
+                is_segment_start: false,
+                in_basename: false,
+                accent: None,
+                tail: None,
             },
             "target_idx differs but eq"
         );
@@ -245,7 +892,12 @@ mod tests {
             Occurrence {
                 char: 'b',
                 target_idx: 0,
-                is_start: true
+                is_start: true,
+                start_kind: Some(WordStart::String),
+                is_segment_start: false,
+                in_basename: false,
+                accent: None,
+                tail: None,
             },
             "char differs but eq"
         );
@@ -255,7 +907,7 @@ mod tests {
     fn occurrences() {
         let t = "SoccerCartoonController";
 
-        let mut occs = build_occurrences(&process_query("scc"), t, true);
+        let mut occs = occurrences_of("scc", t);
 
         assert_eq!(occs.len(), 2);
 
@@ -267,6 +919,11 @@ mod tests {
                 char: 'S',
                 target_idx: 0,
                 is_start: true,
+                start_kind: Some(WordStart::String),
+                is_segment_start: false,
+                in_basename: false,
+                accent: None,
+                tail: None,
             }]
         );
 
@@ -279,21 +936,41 @@ mod tests {
                     char: 'c',
                     target_idx: 2,
                     is_start: false,
+                    start_kind: None,
+                    is_segment_start: false,
+                    in_basename: false,
+                    accent: None,
+                    tail: None,
                 },
                 Occurrence {
                     char: 'c',
                     target_idx: 3,
                     is_start: false,
+                    start_kind: None,
+                    is_segment_start: false,
+                    in_basename: false,
+                    accent: None,
+                    tail: None,
                 },
                 Occurrence {
                     char: 'C',
                     target_idx: 6,
                     is_start: true,
+                    start_kind: Some(WordStart::CaseChange),
+                    is_segment_start: false,
+                    in_basename: false,
+                    accent: None,
+                    tail: None,
                 },
                 Occurrence {
                     char: 'C',
                     target_idx: 13,
                     is_start: true,
+                    start_kind: Some(WordStart::CaseChange),
+                    is_segment_start: false,
+                    in_basename: false,
+                    accent: None,
+                    tail: None,
                 },
             ]
         );
@@ -303,7 +980,7 @@ mod tests {
     fn occurrences_2() {
         let t = "SccsCoolController";
 
-        let mut occs = build_occurrences(&process_query("scc"), t, true);
+        let mut occs = occurrences_of("scc", t);
 
         assert_eq!(occs.len(), 2);
 
@@ -316,11 +993,21 @@ mod tests {
                     char: 'S',
                     target_idx: 0,
                     is_start: true,
+                    start_kind: Some(WordStart::String),
+                    is_segment_start: false,
+                    in_basename: false,
+                    accent: None,
+                    tail: None,
                 },
                 Occurrence {
                     char: 's',
                     target_idx: 3,
                     is_start: false,
+                    start_kind: None,
+                    is_segment_start: false,
+                    in_basename: false,
+                    accent: None,
+                    tail: None,
                 }
             ]
         );
@@ -334,21 +1021,41 @@ mod tests {
                     char: 'c',
                     target_idx: 1,
                     is_start: false,
+                    start_kind: None,
+                    is_segment_start: false,
+                    in_basename: false,
+                    accent: None,
+                    tail: None,
                 },
                 Occurrence {
                     char: 'c',
                     target_idx: 2,
                     is_start: false,
+                    start_kind: None,
+                    is_segment_start: false,
+                    in_basename: false,
+                    accent: None,
+                    tail: None,
                 },
                 Occurrence {
                     char: 'C',
                     target_idx: 4,
                     is_start: true,
+                    start_kind: Some(WordStart::CaseChange),
+                    is_segment_start: false,
+                    in_basename: false,
+                    accent: None,
+                    tail: None,
                 },
                 Occurrence {
                     char: 'C',
                     target_idx: 8,
                     is_start: true,
+                    start_kind: Some(WordStart::CaseChange),
+                    is_segment_start: false,
+                    in_basename: false,
+                    accent: None,
+                    tail: None,
                 },
             ]
         );
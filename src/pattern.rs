@@ -0,0 +1,581 @@
+use std::{borrow::Borrow, mem};
+
+#[cfg(feature = "rayon")]
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    parsing::{
+        build_occurrences, condense, process_query, process_target, unit_str, CharSet, Occurrences,
+        ParseOptions, QueryChar, TargetChar, TargetChars,
+    },
+    ranking::{RankCollector, Ranked, Ranking},
+    scoring::Scorer,
+    search::{CaseMatching, FuzzySearcher},
+    target::PreparedTarget,
+    Explanation, Match,
+};
+
+/// A query compiled for repeated matching, see [`FuzzySearch::compile`](crate::FuzzySearch::compile).
+///
+/// The query is processed only once and the state used while matching is reused
+/// between targets.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use sublime_fuzzy::FuzzySearch;
+///
+/// let mut pattern = FuzzySearch::with_query("tt").compile();
+///
+/// let matching = vec!["The Two Towers", "tower", "the tower"]
+///     .into_iter()
+///     .filter(|t| pattern.best_match(t).is_some())
+///     .count();
+///
+/// assert_eq!(matching, 2);
+/// ```
+pub struct Pattern<'a, S: ?Sized = dyn Scorer + Sync + 'a> {
+    searcher: FuzzySearcher<'a, S>,
+    query_chars: CharSet,
+    case_insensitive: bool,
+    options: ParseOptions,
+    /// See [`ParseOptions::id`]
+    options_id: u64,
+    ranking: Ranking,
+    target_chars: TargetChars,
+    occurrences: Occurrences,
+}
+
+impl<'a, S: ?Sized> Clone for Pattern<'a, S> {
+    fn clone(&self) -> Self {
+        Pattern {
+            searcher: self.searcher.clone(),
+            query_chars: self.query_chars.clone(),
+            case_insensitive: self.case_insensitive,
+            options: self.options.clone(),
+            options_id: self.options_id,
+            ranking: self.ranking,
+            target_chars: self.target_chars.clone(),
+            occurrences: self.occurrences.clone(),
+        }
+    }
+}
+
+impl<'a, S: Scorer + ?Sized> Pattern<'a, S> {
+    pub(crate) fn new(
+        query: &str,
+        scorer: &'a S,
+        case: CaseMatching,
+        options: ParseOptions,
+    ) -> Self {
+        let processed_query = process_query(query, &options);
+        let case = case.resolve(&processed_query);
+
+        let searcher = FuzzySearcher::new(processed_query, scorer, case, &options);
+
+        Pattern {
+            query_chars: condense(searcher.query(), case.is_insensitive()),
+            searcher,
+            case_insensitive: case.is_insensitive(),
+            options_id: options.id(),
+            options,
+            ranking: Ranking::default(),
+            target_chars: TargetChars::new(),
+            occurrences: Occurrences::new(),
+        }
+    }
+
+    /// Returns `true` if every target matched by this pattern is also matched by `previous`.
+    ///
+    /// That is the case if the query chars of `previous` appear in the same order in this query,
+    /// each matching case at least as strictly as in `previous`. An empty `previous` query never
+    /// matches anything so it is never narrowed.
+    ///
+    /// With a maximum gap, chars inserted between the chars of `previous` can make matches
+    /// possible that were too far apart before, so they have to appear next to each other.
+    /// Chars swapped across the start or end of `previous` can do the same, so such queries
+    /// are never narrowed if typos are allowed as well.
+    ///
+    /// With typos, targets only match `previous` if at least one of its chars can be matched.
+    /// If all of them can be skipped as typos in this query, targets without any of them can
+    /// match this query, so queries no longer than the maximum count of typos are never
+    /// narrowed.
+    pub(crate) fn narrows(&self, previous: &Pattern<'a, S>) -> bool {
+        if self.options != previous.options
+            || self.searcher.max_gap() != previous.searcher.max_gap()
+            || self.searcher.max_typos() != previous.searcher.max_typos()
+            || (self.searcher.max_gap().is_some() && self.searcher.max_typos() > 0)
+            || previous.searcher.query().len() <= previous.searcher.max_typos()
+            || previous.searcher.query().is_empty()
+        {
+            return false;
+        }
+
+        let own = self.searcher.query();
+
+        let narrows_from = |start: usize| {
+            let mut chars = own[start..].iter();
+
+            previous.searcher.query().iter().all(|qc| {
+                let match_case = previous.searcher.matches_case(qc);
+                let covers = |own: &QueryChar| {
+                    own.lower == qc.lower
+                        && own.tail == qc.tail
+                        && (!match_case
+                            || (self.searcher.matches_case(own) && own.original == qc.original))
+                };
+
+                if self.searcher.max_gap().is_some() {
+                    chars.next().is_some_and(covers)
+                } else {
+                    chars.any(covers)
+                }
+            })
+        };
+
+        if self.searcher.max_gap().is_some() {
+            (0..own.len()).any(narrows_from)
+        } else {
+            narrows_from(0)
+        }
+    }
+
+    /// Finds the best match of the query in `target`.
+    ///
+    /// Always tries to match the _full_ pattern. A partial match is considered
+    /// invalid and will return [`None`]. Will also return [`None`] in case the query or
+    /// target string are empty.
+    pub fn best_match(&mut self, target: &str) -> Option<Match> {
+        let mut target_chars = mem::take(&mut self.target_chars);
+
+        process_target(target, &self.options, &mut target_chars);
+
+        let m = self.match_chars(target, &target_chars);
+
+        self.target_chars = target_chars;
+
+        m
+    }
+
+    /// Finds the best match of the query in a target that was prepared up front.
+    ///
+    /// Same as [`Pattern::best_match`], but skips processing the target string. Targets
+    /// prepared with a different configuration than this pattern are processed again, see
+    /// [`Pattern::prepare`].
+    pub fn best_match_prepared(&mut self, target: &PreparedTarget) -> Option<Match> {
+        if target.options_id() != self.options_id {
+            return self.best_match(target.as_str());
+        }
+
+        self.match_chars(target.as_str(), target.chars())
+    }
+
+    /// Prepares `target` with the configuration of this pattern, see [`PreparedTarget`].
+    pub fn prepare(&self, target: &str) -> PreparedTarget {
+        PreparedTarget::with_options(target, &self.options)
+    }
+
+    /// Finds the best match of the query in `target` and explains how its score was computed,
+    /// see [`FuzzySearch::explain`](crate::FuzzySearch::explain).
+    pub fn explain(&mut self, target: &str) -> Option<Explanation> {
+        let mut target_chars = mem::take(&mut self.target_chars);
+
+        process_target(target, &self.options, &mut target_chars);
+
+        let explanation = self.explain_chars(target, &target_chars);
+
+        self.target_chars = target_chars;
+
+        explanation
+    }
+
+    fn explain_chars(&mut self, string: &str, target: &[TargetChar]) -> Option<Explanation> {
+        let m = self.match_units(string, target)?;
+
+        let mut chars = self.searcher.explain(&m, &self.occurrences);
+
+        for c in &mut chars {
+            c.char_idx = target[c.char_match.target_idx()].char_idx;
+        }
+
+        Some(Explanation::new(
+            resolve_positions(m, string, target),
+            chars,
+        ))
+    }
+
+    fn match_chars(&mut self, string: &str, target: &[TargetChar]) -> Option<Match> {
+        self.match_units(string, target)
+            .map(|m| resolve_positions(m, string, target))
+    }
+
+    /// Finds the best match of the query in `target`, the units of `string`. The matched
+    /// indices of the match point at units.
+    fn match_units(&mut self, string: &str, target: &[TargetChar]) -> Option<Match> {
+        if self.searcher.query().is_empty() || target.is_empty() {
+            return None;
+        }
+
+        build_occurrences(
+            &self.query_chars,
+            string,
+            target,
+            self.case_insensitive,
+            &self.options,
+            &mut self.occurrences,
+        );
+
+        let mut m = self.searcher.best_match(&self.occurrences)?;
+
+        m.set_best_score(self.searcher.best_score());
+
+        Some(m)
+    }
+
+    /// Sets the order of ranked candidates, see
+    /// [`FuzzySearch::rank_by`](crate::FuzzySearch::rank_by).
+    pub(crate) fn set_ranking(&mut self, ranking: Ranking) {
+        self.ranking = ranking;
+    }
+
+    /// Sets the score matches have to reach, see
+    /// [`FuzzySearch::min_score`](crate::FuzzySearch::min_score).
+    pub(crate) fn set_min_score(&mut self, min_score: Option<isize>) {
+        self.searcher.set_min_score(min_score);
+    }
+
+    pub(crate) fn min_score(&self) -> Option<isize> {
+        self.searcher.min_score()
+    }
+
+    /// Sets the maximum count of chars between matched chars, see
+    /// [`FuzzySearch::max_gap`](crate::FuzzySearch::max_gap).
+    pub(crate) fn set_max_gap(&mut self, max_gap: Option<usize>) {
+        self.searcher.set_max_gap(max_gap);
+    }
+
+    /// Sets the count of query chars that can be skipped or swapped, see
+    /// [`FuzzySearch::max_typos`](crate::FuzzySearch::max_typos).
+    pub(crate) fn set_max_typos(&mut self, max_typos: usize) {
+        self.searcher.set_max_typos(max_typos);
+    }
+
+    /// Returns a collector for ranking with the configuration of this pattern.
+    pub(crate) fn rank_collector<T>(&self, limit: Option<usize>) -> RankCollector<T> {
+        RankCollector::new(limit, self.ranking)
+    }
+
+    /// Matches the query against every candidate and returns the matching ones, best match first.
+    ///
+    /// Candidates are ordered by the [`Ranking`] of the search this pattern was compiled from,
+    /// by default candidates with equal scores keep their input order.
+    pub fn rank<I>(&mut self, candidates: I) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.rank_limited(candidates, None)
+    }
+
+    /// Like [`Pattern::rank`], but only returns the best `limit` matches.
+    ///
+    /// Keeps at most `limit` matches around while ranking instead of sorting
+    /// all matching candidates.
+    pub fn rank_top<I>(&mut self, candidates: I, limit: usize) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.rank_limited(candidates, Some(limit))
+    }
+
+    /// Like [`Pattern::rank`], but for targets that were prepared up front.
+    pub fn rank_prepared<I>(&mut self, candidates: I) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<PreparedTarget>,
+    {
+        self.rank_with(candidates, None, |p, c| p.best_match_prepared(c.borrow()))
+    }
+
+    /// Like [`Pattern::rank_top`], but for targets that were prepared up front.
+    pub fn rank_top_prepared<I>(&mut self, candidates: I, limit: usize) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<PreparedTarget>,
+    {
+        self.rank_with(candidates, Some(limit), |p, c| {
+            p.best_match_prepared(c.borrow())
+        })
+    }
+
+    fn rank_limited<I>(&mut self, candidates: I, limit: Option<usize>) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.rank_with(candidates, limit, |p, c| p.best_match(c.as_ref()))
+    }
+
+    fn rank_with<I, F>(
+        &mut self,
+        candidates: I,
+        limit: Option<usize>,
+        mut match_candidate: F,
+    ) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoIterator,
+        F: FnMut(&mut Self, &I::Item) -> Option<Match>,
+    {
+        let mut collector = self.rank_collector(limit);
+
+        for (i, candidate) in candidates.into_iter().enumerate() {
+            if let Some(m) = match_candidate(self, &candidate) {
+                collector.push(Ranked::new(i, candidate, m));
+            }
+        }
+
+        collector.into_sorted_vec()
+    }
+}
+
+/// Parallel ranking, only available with the `rayon` feature and for `Sync` scorers.
+///
+/// Every worker thread matches with its own copy of the pattern. Results are ordered
+/// exactly like the sequential versions, including ties.
+#[cfg(feature = "rayon")]
+impl<'a, S: Scorer + Sync + ?Sized> Pattern<'a, S> {
+    /// Parallel version of [`Pattern::rank`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::FuzzySearch;
+    ///
+    /// let files = vec!["src/lib.rs", "src/search.rs", "README.md"];
+    ///
+    /// let ranked = FuzzySearch::with_query("sr").compile().par_rank(&files);
+    ///
+    /// assert_eq!(**ranked[0].candidate(), "src/search.rs");
+    /// ```
+    pub fn par_rank<I>(&self, candidates: I) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoParallelIterator,
+        I::Iter: IndexedParallelIterator,
+        I::Item: AsRef<str> + Send,
+    {
+        self.par_rank_with(candidates, None, |p, c| p.best_match(c.as_ref()))
+    }
+
+    /// Parallel version of [`Pattern::rank_top`].
+    ///
+    /// Every worker keeps at most `limit` matches around.
+    pub fn par_rank_top<I>(&self, candidates: I, limit: usize) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoParallelIterator,
+        I::Iter: IndexedParallelIterator,
+        I::Item: AsRef<str> + Send,
+    {
+        self.par_rank_with(candidates, Some(limit), |p, c| p.best_match(c.as_ref()))
+    }
+
+    /// Parallel version of [`Pattern::rank_prepared`].
+    pub fn par_rank_prepared<I>(&self, candidates: I) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoParallelIterator,
+        I::Iter: IndexedParallelIterator,
+        I::Item: Borrow<PreparedTarget> + Send,
+    {
+        self.par_rank_with(candidates, None, |p, c| p.best_match_prepared(c.borrow()))
+    }
+
+    /// Parallel version of [`Pattern::rank_top_prepared`].
+    pub fn par_rank_top_prepared<I>(&self, candidates: I, limit: usize) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoParallelIterator,
+        I::Iter: IndexedParallelIterator,
+        I::Item: Borrow<PreparedTarget> + Send,
+    {
+        self.par_rank_with(candidates, Some(limit), |p, c| {
+            p.best_match_prepared(c.borrow())
+        })
+    }
+
+    fn par_rank_with<I, F>(
+        &self,
+        candidates: I,
+        limit: Option<usize>,
+        match_candidate: F,
+    ) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoParallelIterator,
+        I::Iter: IndexedParallelIterator,
+        I::Item: Send,
+        F: Fn(&mut Self, &I::Item) -> Option<Match> + Sync,
+    {
+        candidates
+            .into_par_iter()
+            .enumerate()
+            .fold(
+                || (self.clone(), self.rank_collector(limit)),
+                |(mut pattern, mut collector), (i, candidate)| {
+                    if let Some(m) = match_candidate(&mut pattern, &candidate) {
+                        collector.push(Ranked::new(i, candidate, m));
+                    }
+
+                    (pattern, collector)
+                },
+            )
+            .map(|(_, collector)| collector)
+            .reduce(|| self.rank_collector(limit), RankCollector::merge)
+            .into_sorted_vec()
+    }
+}
+
+/// Resolves the matched indices of `m`, which point at units of `target`, to the chars of
+/// `string`.
+fn resolve_positions(mut m: Match, string: &str, target: &[TargetChar]) -> Match {
+    let mut matched = Vec::with_capacity(m.matched_indices().len());
+    let mut bytes = Vec::with_capacity(m.matched_indices().len());
+
+    for &unit_idx in m.matched_indices() {
+        let unit = &target[unit_idx];
+
+        for (i, (offset, c)) in unit_str(string, target, unit_idx)
+            .char_indices()
+            .enumerate()
+        {
+            if matched.last() != Some(&(unit.char_idx + i)) {
+                let byte_idx = unit.byte_idx + offset;
+
+                matched.push(unit.char_idx + i);
+                bytes.push(byte_idx..byte_idx + c.len_utf8());
+            }
+        }
+    }
+
+    let target_len = target.last().map_or(0, |unit| {
+        unit.char_idx + unit_str(string, target, target.len() - 1).chars().count()
+    });
+
+    m.set_positions(matched, bytes, target_len);
+
+    m
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{best_match, FuzzySearch, PreparedTarget};
+
+    #[test]
+    fn reuse_matches_single_search() {
+        let targets = vec![
+            "SoccerCartoonController",
+            "some search thing",
+            "nothing",
+            "SccsCoolController",
+            "",
+        ];
+
+        let mut pattern = FuzzySearch::with_query("sc").compile();
+
+        for t in targets {
+            assert_eq!(
+                pattern.best_match(t).map(|m| m.score()),
+                best_match("sc", t).map(|m| m.score()),
+                "Differs from single search for {}",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn stale_occurrences_cleared() {
+        let mut pattern = FuzzySearch::with_query("ab").compile();
+
+        assert!(pattern.best_match("ab").is_some());
+        assert!(pattern.best_match("b").is_none());
+        assert!(pattern.best_match("a").is_none());
+    }
+
+    #[test]
+    fn prepared_matches_unprepared() {
+        let targets = vec![
+            "SoccerCartoonController",
+            "some search thing",
+            "nothing",
+            "",
+        ];
+
+        let mut pattern = FuzzySearch::with_query("sc").compile();
+
+        for t in targets {
+            assert_eq!(
+                pattern
+                    .best_match_prepared(&PreparedTarget::new(t))
+                    .map(|m| m.score()),
+                pattern.best_match(t).map(|m| m.score()),
+                "Differs from unprepared target for {}",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn prepared_with_other_configuration() {
+        let target = PreparedTarget::new("src/lib.rs");
+        let search = FuzzySearch::new("lib", "src/lib.rs").path_aware();
+
+        assert_eq!(
+            search
+                .clone()
+                .best_match_prepared(&target)
+                .map(|m| m.score()),
+            search.best_match().map(|m| m.score())
+        );
+    }
+
+    #[test]
+    fn rank_prepared() {
+        let targets = vec!["nothing", "sc", "some search thing"]
+            .into_iter()
+            .map(PreparedTarget::new)
+            .collect::<Vec<PreparedTarget>>();
+
+        let mut pattern = FuzzySearch::with_query("sc").compile();
+
+        let ranked = pattern.rank_prepared(&targets);
+
+        assert_eq!(
+            ranked.iter().map(|r| r.index()).collect::<Vec<usize>>(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn par_rank_matches_rank() {
+        let targets = (0..2000)
+            .map(|i| format!("src/module_{}/some_controller_{}.rs", i % 17, i % 101))
+            .collect::<Vec<String>>();
+
+        let mut pattern = FuzzySearch::with_query("m1scr").compile();
+
+        let indices = |ranked: Vec<crate::Ranked<&String>>| {
+            ranked.iter().map(|r| r.index()).collect::<Vec<usize>>()
+        };
+
+        assert_eq!(
+            indices(pattern.par_rank(&targets)),
+            indices(pattern.rank(&targets))
+        );
+        assert_eq!(
+            indices(pattern.par_rank_top(&targets, 25)),
+            indices(pattern.rank_top(&targets, 25))
+        );
+    }
+}
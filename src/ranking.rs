@@ -0,0 +1,390 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::Match;
+
+/// A candidate that matched the query of a ranking, see [`FuzzySearch::rank`](crate::FuzzySearch::rank).
+#[derive(Clone, Debug)]
+pub struct Ranked<T> {
+    /// Position of the candidate in the ranked input
+    index: usize,
+    candidate: T,
+    match_: Match,
+}
+
+impl<T> Ranked<T> {
+    pub(crate) fn new(index: usize, candidate: T, match_: Match) -> Self {
+        Ranked {
+            index,
+            candidate,
+            match_,
+        }
+    }
+
+    /// Returns the position of the candidate in the input of the ranking.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the matched candidate.
+    pub fn candidate(&self) -> &T {
+        &self.candidate
+    }
+
+    /// Returns the best match of the query in the candidate.
+    pub fn as_match(&self) -> &Match {
+        &self.match_
+    }
+
+    /// Returns the score of the match, see [`Match::score`].
+    pub fn score(&self) -> isize {
+        self.match_.score()
+    }
+
+    /// Splits this into the candidate and its match.
+    pub fn into_parts(self) -> (T, Match) {
+        (self.candidate, self.match_)
+    }
+}
+
+/// A key for ordering ranked candidates, see [`Ranking`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RankKey {
+    /// Higher scores rank higher.
+    Score,
+    /// Matches starting earlier in the target string rank higher.
+    FirstMatch,
+    /// Matches spanning fewer chars (from the first to the last matched char) rank higher.
+    Span,
+    /// Shorter target strings rank higher.
+    TargetLen,
+    /// Candidates earlier in the input rank higher.
+    Index,
+}
+
+/// Decides the order of ranked candidates by comparing a list of keys, see
+/// [`FuzzySearch::rank_by`](crate::FuzzySearch::rank_by).
+///
+/// Keys are compared in order, later keys only break ties of earlier keys. Candidates that are
+/// equal in all keys keep their input order.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use sublime_fuzzy::{FuzzySearch, RankKey, Ranking};
+///
+/// let files = vec!["src/main.rs", "main.rs"];
+///
+/// let ranked = FuzzySearch::with_query("main")
+///     .rank_by(Ranking::new(&[RankKey::TargetLen, RankKey::Score]))
+///     .rank(&files);
+///
+/// assert_eq!(*ranked[0].candidate(), &"main.rs");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ranking {
+    keys: [RankKey; KEY_COUNT],
+    len: usize,
+}
+
+/// Count of different [`RankKey`]s
+const KEY_COUNT: usize = 5;
+
+impl Ranking {
+    /// Creates a ranking comparing `keys` in order.
+    ///
+    /// Repeated keys are ignored, they can't break ties of their first occurrence.
+    pub fn new(keys: &[RankKey]) -> Self {
+        let mut ranking = Ranking {
+            keys: [RankKey::Score; KEY_COUNT],
+            len: 0,
+        };
+
+        for &key in keys {
+            if !ranking.keys().contains(&key) {
+                ranking.keys[ranking.len] = key;
+                ranking.len += 1;
+            }
+        }
+
+        ranking
+    }
+
+    /// Creates a ranking by score only, ties keep their input order (this is also the default).
+    pub fn by_score() -> Self {
+        Ranking::new(&[RankKey::Score])
+    }
+
+    /// Creates a ranking by score, breaking ties by the length of the target string and then
+    /// by the position of the first matched char.
+    pub fn by_score_then_length() -> Self {
+        Ranking::new(&[RankKey::Score, RankKey::TargetLen, RankKey::FirstMatch])
+    }
+
+    /// Returns the compared keys.
+    pub fn keys(&self) -> &[RankKey] {
+        &self.keys[..self.len]
+    }
+
+    /// Orders `a` before `b` if it ranks higher.
+    pub fn compare<T>(&self, a: &Ranked<T>, b: &Ranked<T>) -> Ordering {
+        self.keys()
+            .iter()
+            .fold(Ordering::Equal, |ordering, key| {
+                ordering.then_with(|| compare_key(*key, a, b))
+            })
+            .then_with(|| a.index.cmp(&b.index))
+    }
+}
+
+impl Default for Ranking {
+    /// Creates the default ranking, see [`Ranking::by_score`].
+    fn default() -> Self {
+        Ranking::by_score()
+    }
+}
+
+fn compare_key<T>(key: RankKey, a: &Ranked<T>, b: &Ranked<T>) -> Ordering {
+    match key {
+        RankKey::Score => b.score().cmp(&a.score()),
+        RankKey::FirstMatch => first_matched(a).cmp(&first_matched(b)),
+        RankKey::Span => span(a).cmp(&span(b)),
+        RankKey::TargetLen => a.match_.target_len().cmp(&b.match_.target_len()),
+        RankKey::Index => a.index.cmp(&b.index),
+    }
+}
+
+fn first_matched<T>(ranked: &Ranked<T>) -> Option<usize> {
+    ranked.match_.matched_indices().next().cloned()
+}
+
+fn span<T>(ranked: &Ranked<T>) -> usize {
+    let mut matched = ranked.match_.matched_indices();
+
+    match (matched.next(), matched.next_back()) {
+        (Some(first), Some(last)) => last - first + 1,
+        (Some(_), None) => 1,
+        _ => 0,
+    }
+}
+
+/// Heap entry ordering the _worst_ ranked entry to the top of a [`BinaryHeap`].
+struct Worst<T> {
+    ranked: Ranked<T>,
+    ranking: Ranking,
+}
+
+impl<T> Ord for Worst<T> {
+    fn cmp(&self, other: &Worst<T>) -> Ordering {
+        self.ranking.compare(&self.ranked, &other.ranked)
+    }
+}
+
+impl<T> PartialOrd for Worst<T> {
+    fn partial_cmp(&self, other: &Worst<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Eq for Worst<T> {}
+
+impl<T> PartialEq for Worst<T> {
+    fn eq(&self, other: &Worst<T>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+/// Collects ranked candidates, keeping at most `limit` of them.
+pub(crate) struct RankCollector<T> {
+    limit: Option<usize>,
+    ranking: Ranking,
+    heap: BinaryHeap<Worst<T>>,
+    all: Vec<Ranked<T>>,
+}
+
+impl<T> RankCollector<T> {
+    pub(crate) fn new(limit: Option<usize>, ranking: Ranking) -> Self {
+        RankCollector {
+            limit,
+            ranking,
+            heap: BinaryHeap::with_capacity(limit.map_or(0, |l| l + 1)),
+            all: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, ranked: Ranked<T>) {
+        match self.limit {
+            None => self.all.push(ranked),
+            Some(0) => {}
+            Some(limit) => {
+                self.heap.push(Worst {
+                    ranked,
+                    ranking: self.ranking,
+                });
+
+                if self.heap.len() > limit {
+                    self.heap.pop();
+                }
+            }
+        }
+    }
+
+    /// Adds the candidates collected by `other`.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn merge(mut self, other: RankCollector<T>) -> Self {
+        for ranked in other.all {
+            self.push(ranked);
+        }
+
+        for worst in other.heap {
+            self.push(worst.ranked);
+        }
+
+        self
+    }
+
+    /// Returns the collected candidates, best ranked first.
+    pub(crate) fn into_sorted_vec(self) -> Vec<Ranked<T>> {
+        match self.limit {
+            None => {
+                let ranking = self.ranking;
+                let mut all = self.all;
+
+                all.sort_by(|a, b| ranking.compare(a, b));
+
+                all
+            }
+            Some(_) => self
+                .heap
+                .into_sorted_vec()
+                .into_iter()
+                .map(|w| w.ranked)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Match;
+
+    use super::{RankCollector, RankKey, Ranked, Ranking};
+
+    fn ranked(index: usize, score: isize) -> Ranked<usize> {
+        Ranked::new(index, index, Match::with_matched(score, 0, vec![0]))
+    }
+
+    fn indices(ranking: RankCollector<usize>) -> Vec<usize> {
+        ranking
+            .into_sorted_vec()
+            .iter()
+            .map(|r| r.index())
+            .collect()
+    }
+
+    #[test]
+    fn sorts_by_score() {
+        let mut ranking = RankCollector::new(None, Ranking::default());
+
+        for (i, score) in [4, 12, 8, 12].iter().enumerate() {
+            ranking.push(ranked(i, *score));
+        }
+
+        assert_eq!(indices(ranking), vec![1, 3, 2, 0]);
+    }
+
+    #[test]
+    fn limit_keeps_best() {
+        let mut ranking = RankCollector::new(Some(2), Ranking::default());
+
+        for (i, score) in [4, 12, 8, 12, 16].iter().enumerate() {
+            ranking.push(ranked(i, *score));
+        }
+
+        assert_eq!(indices(ranking), vec![4, 1]);
+    }
+
+    #[test]
+    fn limit_zero() {
+        let mut ranking = RankCollector::new(Some(0), Ranking::default());
+
+        ranking.push(ranked(0, 4));
+
+        assert!(indices(ranking).is_empty());
+    }
+
+    #[test]
+    fn break_ties_by_length() {
+        let ranked = |index: usize, target_len: usize, first: usize| {
+            let mut m = Match::with_matched(8, 0, vec![first]);
+            let bytes = (first..first + 1).map(|i| i..i + 1).collect();
+            m.set_positions(vec![first], bytes, target_len);
+
+            Ranked::new(index, index, m)
+        };
+
+        for &(limit, ref expected) in &[(None, vec![2, 3, 1, 0]), (Some(3), vec![2, 3, 1])] {
+            let mut ranking = RankCollector::new(limit, Ranking::by_score_then_length());
+
+            ranking.push(ranked(0, 20, 0));
+            ranking.push(ranked(1, 10, 4));
+            ranking.push(ranked(2, 10, 2));
+            ranking.push(ranked(3, 10, 2));
+
+            assert_eq!(&indices(ranking), expected);
+        }
+    }
+
+    #[test]
+    fn custom_keys() {
+        let ranked = |index: usize, score: isize, matched: Vec<usize>| {
+            let bytes = matched.iter().map(|&i| i..i + 1).collect();
+            let mut m = Match::with_matched(score, 0, matched.clone());
+            m.set_positions(matched, bytes, 10);
+
+            Ranked::new(index, index, m)
+        };
+
+        let candidates = [
+            ranked(0, 8, vec![4, 9]),
+            ranked(1, 4, vec![0, 1]),
+            ranked(2, 8, vec![2, 4]),
+        ];
+
+        let indices = |keys: &[RankKey]| {
+            let mut ranking = RankCollector::new(None, Ranking::new(keys));
+
+            for r in candidates.iter().cloned() {
+                ranking.push(r);
+            }
+
+            indices(ranking)
+        };
+
+        assert_eq!(indices(&[RankKey::Score]), vec![0, 2, 1]);
+        assert_eq!(indices(&[RankKey::Span]), vec![1, 2, 0]);
+        assert_eq!(indices(&[RankKey::FirstMatch]), vec![1, 2, 0]);
+        assert_eq!(indices(&[RankKey::Score, RankKey::Span]), vec![2, 0, 1]);
+        assert_eq!(indices(&[RankKey::TargetLen]), vec![0, 1, 2]);
+
+        // Keys read at runtime, e.g. from a config
+        let keys = "span,score"
+            .split(',')
+            .map(|key| match key {
+                "span" => RankKey::Span,
+                _ => RankKey::Score,
+            })
+            .collect::<Vec<RankKey>>();
+
+        assert_eq!(indices(&keys), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn repeated_keys() {
+        let ranking = Ranking::new(&[RankKey::Score, RankKey::Span, RankKey::Score]);
+
+        assert_eq!(ranking.keys(), &[RankKey::Score, RankKey::Span]);
+        assert_eq!(ranking, Ranking::new(&[RankKey::Score, RankKey::Span]));
+    }
+}
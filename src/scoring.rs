@@ -1,32 +1,278 @@
+use crate::boundary::WordStart;
+
+/// Computes the score of a [`Match`](crate::Match) from its matched chars, see
+/// [`FuzzySearch::score_with`](crate::FuzzySearch::score_with).
+///
+/// The score of a match is the sum of the scores returned by the callbacks for all of its
+/// matched chars, consecutive runs and gaps. [`Scoring`] is the default implementation.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use sublime_fuzzy::{CharMatch, FuzzySearch, Scorer};
+///
+/// /// Only counts matched word starts.
+/// struct WordStarts;
+///
+/// impl Scorer for WordStarts {
+///     fn score_char(&self, c: &CharMatch) -> isize {
+///         c.word_start().is_some() as isize
+///     }
+///
+///     fn score_consecutive(&self, _run: usize) -> isize {
+///         0
+///     }
+///
+///     fn score_gap(&self, _gap: usize) -> isize {
+///         0
+///     }
+/// }
+///
+/// let m = FuzzySearch::new("scc", "SoccerCartoonController")
+///     .score_with(&WordStarts)
+///     .best_match()
+///     .unwrap();
+///
+/// assert_eq!(m.score(), 3);
+/// ```
+pub trait Scorer {
+    /// Returns the score of a single matched char.
+    fn score_char(&self, c: &CharMatch) -> isize;
+
+    /// Returns the score for a matched char following `run` consecutive matched chars.
+    ///
+    /// Called once for every matched char directly following another matched char, so `run`
+    /// is `1` for the second char of a run, `2` for the third and so on.
+    fn score_consecutive(&self, run: usize) -> isize;
+
+    /// Returns an additional score for a match ending with a run of consecutive chars, `run`
+    /// is the run of its last matched char as passed to [`Scorer::score_consecutive`] (`0` if
+    /// it doesn't follow a matched char).
+    ///
+    /// The default implementation returns `0`.
+    fn score_final_run(&self, run: usize) -> isize {
+        let _ = run;
+
+        0
+    }
+
+    /// Returns the score for `gap` unmatched chars between two matched chars (usually negative).
+    fn score_gap(&self, gap: usize) -> isize;
+
+    /// Returns the score for a query char that was not matched or swapped with the next one
+    /// (usually negative), see [`FuzzySearch::max_typos`](crate::FuzzySearch::max_typos).
+    ///
+    /// The default implementation returns the default [`Scoring::penalty_typo`].
+    fn score_typo(&self) -> isize {
+        -DEFAULT_SCORING.penalty_typo
+    }
+
+    /// Returns the parts of [`Scorer::score_char`], see
+    /// [`FuzzySearch::explain`](crate::FuzzySearch::explain).
+    ///
+    /// The default implementation reports the whole score of the char as
+    /// [`ScoreBreakdown::other`].
+    fn explain_char(&self, c: &CharMatch) -> ScoreBreakdown {
+        ScoreBreakdown {
+            other: self.score_char(c),
+            ..ScoreBreakdown::default()
+        }
+    }
+}
+
+/// The bonuses/penalties applied for a matched char, see
+/// [`FuzzySearch::explain`](crate::FuzzySearch::explain).
+///
+/// Penalties are negative.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScoreBreakdown {
+    /// See [`Scoring::bonus_word_start`] and [`Scoring::word_start_bonuses`].
+    pub word_start: isize,
+    /// See [`Scoring::bonus_match_case`].
+    pub match_case: isize,
+    /// See [`Scoring::bonus_match_accent`].
+    pub match_accent: isize,
+    /// See [`Scoring::bonus_segment_start`].
+    pub segment_start: isize,
+    /// See [`Scoring::bonus_basename`].
+    pub basename: isize,
+    /// See [`Scoring::bonus_basename_end`].
+    pub basename_end: isize,
+    /// See [`Scoring::bonus_prefix`] and [`Scoring::penalty_leading`].
+    pub leading: isize,
+    /// Bonus for consecutive matched chars, see [`Scorer::score_consecutive`].
+    pub consecutive: isize,
+    /// Penalty for the unmatched chars before this char, see [`Scorer::score_gap`].
+    pub gap: isize,
+    /// Penalty for typos, see [`Scorer::score_typo`]. Applied to the char matched after a
+    /// missing query char (or the last matched char), and to the first char of swapped chars.
+    pub typo: isize,
+    /// Scores of custom [`Scorer`]s that don't break down their scores.
+    pub other: isize,
+}
+
+impl ScoreBreakdown {
+    /// Returns the sum of all bonuses/penalties.
+    pub fn total(&self) -> isize {
+        self.word_start
+            + self.match_case
+            + self.match_accent
+            + self.segment_start
+            + self.basename
+            + self.basename_end
+            + self.leading
+            + self.consecutive
+            + self.gap
+            + self.typo
+            + self.other
+    }
+}
+
+/// A query char matched in the target string, see [`Scorer::score_char`].
+#[derive(Clone, Debug)]
+pub struct CharMatch {
+    pub(crate) query_idx: usize,
+    pub(crate) target_idx: usize,
+    pub(crate) query_len: usize,
+    pub(crate) word_start: Option<WordStart>,
+    pub(crate) matches_case: Option<bool>,
+    pub(crate) matches_accent: Option<bool>,
+    pub(crate) is_segment_start: bool,
+    pub(crate) in_basename: bool,
+}
+
+impl CharMatch {
+    /// Returns the index of the matched char in the query.
+    pub fn query_idx(&self) -> usize {
+        self.query_idx
+    }
+
+    /// Returns the index of the matched char (or grapheme cluster) in the target string.
+    pub fn target_idx(&self) -> usize {
+        self.target_idx
+    }
+
+    /// Returns `true` if this is the first char of the query.
+    pub fn is_first(&self) -> bool {
+        self.query_idx == 0
+    }
+
+    /// Returns `true` if this is the last char of the query.
+    pub fn is_last(&self) -> bool {
+        self.query_idx + 1 == self.query_len
+    }
+
+    /// Returns the kind of word start if the matched char starts a word.
+    pub fn word_start(&self) -> Option<WordStart> {
+        self.word_start
+    }
+
+    /// Returns whether the case of the query char matches the target char, or [`None`] if
+    /// the search is case sensitive.
+    pub fn matches_case(&self) -> Option<bool> {
+        self.matches_case
+    }
+
+    /// Returns whether the accents of the query char match the target char, or [`None`] if
+    /// the search is not accent insensitive.
+    pub fn matches_accent(&self) -> Option<bool> {
+        self.matches_accent
+    }
+
+    /// Returns `true` if the matched char starts a path segment (only for path aware searches).
+    pub fn is_segment_start(&self) -> bool {
+        self.is_segment_start
+    }
+
+    /// Returns `true` if the matched char is part of the last path segment (only for path
+    /// aware searches).
+    pub fn in_basename(&self) -> bool {
+        self.in_basename
+    }
+}
+
 pub static DEFAULT_SCORING: Scoring = Scoring {
     bonus_consecutive: 8,
     bonus_word_start: 72,
+    word_start_bonuses: WordStartBonuses {
+        string_start: None,
+        after_path_separator: None,
+        after_joiner: None,
+        after_member: None,
+        case_change: None,
+    },
     bonus_match_case: 8,
     penalty_distance: 4,
+    bonus_prefix: 0,
+    penalty_leading: 0,
+    bonus_match_accent: 8,
+    bonus_segment_start: 16,
+    bonus_basename: 8,
+    bonus_basename_end: 32,
+    penalty_typo: 64,
 };
 
 /// Bonuses/penalties used for scoring a [`Match`](crate::matching::Match).
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", serde(default))]
 pub struct Scoring {
-    /// `current_consecutive_count * bonus_consecutive` will be added for every
-    /// consecutive char match.
+    /// Added for consecutive char matches. A char following `run` consecutive matched chars
+    /// adds `run * bonus_consecutive` for itself and for each of the chars before it in the run,
+    /// `(run + run²) * bonus_consecutive` in total.
     ///
-    /// `1 * bonus` for the first consecutive match, `2 * bonus` for
-    /// the second, etc.
+    /// The run ending a match is added once more for each of its chars before the last one,
+    /// `run² * bonus_consecutive` for a last char following `run` matched chars.
     pub bonus_consecutive: isize,
     /// Added when a query char matches a word start.
+    ///
+    /// Used for all kinds of word starts without a bonus in [`Scoring::word_start_bonuses`].
     pub bonus_word_start: isize,
+    /// Bonuses for specific kinds of word starts, replacing [`Scoring::bonus_word_start`].
+    pub word_start_bonuses: WordStartBonuses,
     /// Added when the matched query char also matches the case of the target char.
     ///
     /// Only applied if the search is case insensitive.
     pub bonus_match_case: isize,
     /// Subtracted from the score for every char between two matches.
     pub penalty_distance: isize,
+    /// Added when the first query char matches the first char of the target string.
+    ///
+    /// Defaults to 0, where it does not matter where in the target string a match starts.
+    pub bonus_prefix: isize,
+    /// Subtracted from the score for every char before the first matched char.
+    ///
+    /// Defaults to 0, where it does not matter where in the target string a match starts.
+    pub penalty_leading: isize,
+    /// Added when the matched query char also matches the accents of the target char.
+    ///
+    /// Only applied if the search is accent insensitive.
+    pub bonus_match_accent: isize,
+    /// Added when a query char matches the first char of a path segment (directly after `/`).
+    ///
+    /// Only applied if the search is path aware.
+    pub bonus_segment_start: isize,
+    /// Added for every query char matched in the last path segment (the file name).
+    ///
+    /// Only applied if the search is path aware.
+    pub bonus_basename: isize,
+    /// Added once if the last query char is matched in the last path segment.
+    ///
+    /// Only applied if the search is path aware.
+    pub bonus_basename_end: isize,
+    /// Subtracted from the score for every query char that was not matched or swapped with
+    /// the next one.
+    ///
+    /// Only applied if the search allows typos.
+    pub penalty_typo: isize,
 }
 
 impl Scoring {
     /// Creates a new configuration with the given bonuses/penalties.
+    ///
+    /// Bonuses/penalties not given use their default values.
     pub fn new(
         bonus_consecutive: isize,
         bonus_word_start: isize,
@@ -38,9 +284,25 @@ impl Scoring {
             bonus_word_start,
             bonus_match_case,
             penalty_distance,
+            ..DEFAULT_SCORING.clone()
         }
     }
 
+    /// Returns the bonus for matching a word start of the given kind.
+    pub fn word_start_bonus(&self, kind: WordStart) -> isize {
+        let bonuses = &self.word_start_bonuses;
+
+        match kind {
+            WordStart::String => bonuses.string_start,
+            WordStart::PathSeparator => bonuses.after_path_separator,
+            WordStart::Joiner => bonuses.after_joiner,
+            WordStart::Member => bonuses.after_member,
+            WordStart::CaseChange => bonuses.case_change,
+            WordStart::Separator | WordStart::Other => None,
+        }
+        .unwrap_or(self.bonus_word_start)
+    }
+
     /// Creates a configuration that emphasizes matching word starts (this is also the default).
     pub fn emphasize_word_starts() -> Self {
         Self::default()
@@ -52,9 +314,103 @@ impl Scoring {
     }
 }
 
+/// Scores every matched char with the configured bonuses, see the documentation of the fields.
+impl Scorer for Scoring {
+    fn score_char(&self, c: &CharMatch) -> isize {
+        self.explain_char(c).total()
+    }
+
+    fn score_consecutive(&self, run: usize) -> isize {
+        let run = run as isize;
+
+        (run + run * run) * self.bonus_consecutive
+    }
+
+    fn score_final_run(&self, run: usize) -> isize {
+        let run = run as isize;
+
+        run * run * self.bonus_consecutive
+    }
+
+    fn score_gap(&self, gap: usize) -> isize {
+        -(gap as isize) * self.penalty_distance
+    }
+
+    fn score_typo(&self) -> isize {
+        -self.penalty_typo
+    }
+
+    fn explain_char(&self, c: &CharMatch) -> ScoreBreakdown {
+        // Where the match starts is only scored for the first query char
+        let leading = match (c.is_first(), c.target_idx()) {
+            (false, _) => 0,
+            (true, 0) => self.bonus_prefix,
+            (true, idx) => -(idx as isize) * self.penalty_leading,
+        };
+
+        ScoreBreakdown {
+            word_start: c.word_start().map_or(0, |kind| self.word_start_bonus(kind)),
+            match_case: c
+                .matches_case()
+                .map_or(0, |matches| matches as isize * self.bonus_match_case),
+            match_accent: c
+                .matches_accent()
+                .map_or(0, |matches| matches as isize * self.bonus_match_accent),
+            segment_start: c.is_segment_start() as isize * self.bonus_segment_start,
+            basename: c.in_basename() as isize * self.bonus_basename,
+            basename_end: (c.is_last() && c.in_basename()) as isize * self.bonus_basename_end,
+            leading,
+            ..ScoreBreakdown::default()
+        }
+    }
+}
+
 impl Default for Scoring {
     /// Creates a default configuration, see [`Scoring::emphasize_word_starts`].
     fn default() -> Self {
         DEFAULT_SCORING.clone()
     }
 }
+
+/// Bonuses for matching specific kinds of word starts, see [`Scoring::word_start_bonuses`].
+///
+/// Kinds without a bonus (`None`) use [`Scoring::bonus_word_start`]. Which chars start words
+/// is decided by the [`WordBoundary`](crate::WordBoundary) of the search.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use sublime_fuzzy::{FuzzySearch, Scoring, WordStartBonuses};
+///
+/// let scoring = Scoring {
+///     word_start_bonuses: WordStartBonuses {
+///         after_member: Some(96),
+///         ..WordStartBonuses::default()
+///     },
+///     ..Scoring::default()
+/// };
+///
+/// let ranked = FuzzySearch::with_query("t")
+///     .score_with(&scoring)
+///     .rank(&["crate_t", "crate::T"]);
+///
+/// assert_eq!(*ranked[0].candidate(), &"crate::T");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", serde(default))]
+pub struct WordStartBonuses {
+    /// Added when a query char matches the first char of the target string.
+    pub string_start: Option<isize>,
+    /// Added when a query char matches the first char after `/` or `\\`.
+    pub after_path_separator: Option<isize>,
+    /// Added when a query char matches the first char after `_` or `-`.
+    pub after_joiner: Option<isize>,
+    /// Added when a query char matches the first char after `.` or `::`.
+    pub after_member: Option<isize>,
+    /// Added when a query char matches a camelCase hump like `C` in `SoccerCartoon` or `P` in
+    /// `HTMLParser`.
+    pub case_change: Option<isize>,
+}
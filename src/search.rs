@@ -1,16 +1,26 @@
 use std::collections::HashMap;
 
-use matching::Match;
+#[cfg(feature = "rayon")]
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator};
+
+use explain::{CharScore, Explanation};
+use matching::{Match, Typo};
 use parsing::Occurrences;
-use scoring::Scoring;
+use scoring::{CharMatch, Scorer};
 
 use crate::{
-    parsing::{build_occurrences, process_query, Occurrence, QueryChar, QueryChars},
+    boundary::{WordBoundary, WordStart},
+    parsing::{Occurrence, ParseOptions, QueryChar, QueryChars},
+    pattern::Pattern,
+    ranking::{Ranked, Ranking},
     scoring::DEFAULT_SCORING,
+    target::PreparedTarget,
 };
 
 /// Describes a fuzzy search. Alternative to [`best_match`](crate::best_match) which allows for more configuration.
 ///
+/// `S` is the type of the scorer, see [`FuzzySearch::score_with`].
+///
 /// # Examples
 ///
 /// Basic usage:
@@ -27,11 +37,16 @@ use crate::{
 ///
 /// assert!(result.is_some());
 /// ```
-pub struct FuzzySearch<'a> {
+pub struct FuzzySearch<'a, S: ?Sized = dyn Scorer + Sync + 'a> {
     query: &'a str,
     target: &'a str,
-    scoring: Option<&'a Scoring>,
-    case_insensitive: bool,
+    scorer: &'a S,
+    case: CaseMatching,
+    options: ParseOptions,
+    ranking: Ranking,
+    min_score: Option<isize>,
+    max_gap: Option<usize>,
+    max_typos: usize,
 }
 
 impl<'a> FuzzySearch<'a> {
@@ -42,18 +57,42 @@ impl<'a> FuzzySearch<'a> {
         FuzzySearch {
             query,
             target,
-            scoring: None,
-            case_insensitive: true,
+            scorer: &DEFAULT_SCORING,
+            case: CaseMatching::Insensitive,
+            options: ParseOptions::default(),
+            ranking: Ranking::default(),
+            min_score: None,
+            max_gap: None,
+            max_typos: 0,
         }
     }
 
-    /// Use custom scoring values.
+    /// Creates a new search for `query` without a target string.
     ///
-    /// If not specified will use `Scoring::default()`.
-    pub fn score_with(mut self, scoring: &'a Scoring) -> Self {
-        self.scoring = Some(scoring);
+    /// Meant to be used with [`FuzzySearch::rank`] or [`FuzzySearch::rank_top`],
+    /// [`FuzzySearch::best_match`] will always return [`None`].
+    pub fn with_query(query: &'a str) -> Self {
+        FuzzySearch::new(query, "")
+    }
+}
 
-        self
+impl<'a, S: Scorer + ?Sized> FuzzySearch<'a, S> {
+    /// Use custom scoring values, or a custom [`Scorer`].
+    ///
+    /// If not specified will use `Scoring::default()`. The parallel versions of the ranking
+    /// methods (with the `rayon` feature) require the scorer to be `Sync`.
+    pub fn score_with<T: Scorer + ?Sized>(self, scorer: &'a T) -> FuzzySearch<'a, T> {
+        FuzzySearch {
+            query: self.query,
+            target: self.target,
+            scorer,
+            case: self.case,
+            options: self.options,
+            ranking: self.ranking,
+            min_score: self.min_score,
+            max_gap: self.max_gap,
+            max_typos: self.max_typos,
+        }
     }
 
     /// Only match query chars in the target string if case matches.
@@ -61,7 +100,7 @@ impl<'a> FuzzySearch<'a> {
     /// [`Scoring::bonus_match_case`] will not be applied if this is set (because a char match will
     /// always also be a case match).
     pub fn case_sensitive(mut self) -> Self {
-        self.case_insensitive = false;
+        self.case = CaseMatching::Sensitive;
 
         self
     }
@@ -71,141 +110,1069 @@ impl<'a> FuzzySearch<'a> {
     /// If not only the char but also the case matches, [`Scoring::bonus_match_case`] will be added to
     /// the score. If that behavior is not wanted the bonus can be set to 0 with custom scoring.
     pub fn case_insensitive(mut self) -> Self {
-        self.case_insensitive = true;
+        self.case = CaseMatching::Insensitive;
+
+        self
+    }
+
+    /// Ignore case if the query is all lowercase, otherwise match case (like `--smart-case` of
+    /// ripgrep).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::FuzzySearch;
+    ///
+    /// assert!(FuzzySearch::new("readme", "README.md").smart_case().best_match().is_some());
+    /// assert!(FuzzySearch::new("ReadMe", "README.md").smart_case().best_match().is_none());
+    /// ```
+    pub fn smart_case(mut self) -> Self {
+        self.case = CaseMatching::Smart;
+
+        self
+    }
+
+    /// Like [`FuzzySearch::smart_case`], but only the uppercase query chars have to match case,
+    /// lowercase query chars match chars of any case.
+    ///
+    /// [`Scoring::bonus_match_case`] is still added for lowercase query chars matching lowercase
+    /// target chars.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::FuzzySearch;
+    ///
+    /// let matches = |query| {
+    ///     FuzzySearch::new(query, "README.md")
+    ///         .smart_case_per_char()
+    ///         .best_match()
+    ///         .is_some()
+    /// };
+    ///
+    /// assert!(matches("ReadMe"));
+    /// assert!(!matches("readmE.Md"));
+    /// ```
+    pub fn smart_case_per_char(mut self) -> Self {
+        self.case = CaseMatching::SmartPerChar;
+
+        self
+    }
+
+    /// Match extended grapheme clusters instead of chars.
+    ///
+    /// A query cluster only matches a target cluster consisting of the same chars, e.g.
+    /// `e` does not match `é` written with a combining accent. Matches always cover
+    /// complete clusters, so [`Match::continuous_matches`](crate::Match::continuous_matches)
+    /// never splits a cluster. Apart from the first char, chars of a cluster are
+    /// compared case sensitively.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::{format_simple, FuzzySearch};
+    ///
+    /// let target = "Cafe\u{301} 👩‍🔬";
+    /// let m = FuzzySearch::new("e\u{301}👩‍🔬", target)
+    ///     .grapheme_clusters()
+    ///     .best_match()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(format_simple(&m, target, "<", ">"), "Caf<e\u{301}> <👩‍🔬>");
+    /// assert!(FuzzySearch::new("ee", "e\u{301}e").grapheme_clusters().best_match().is_none());
+    /// ```
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn grapheme_clusters(mut self) -> Self {
+        self.options.graphemes = true;
+
+        self
+    }
+
+    /// Ignore accents, e.g. match `e` in `é` and `u` in `ü`.
+    ///
+    /// Accents are stripped from query and target chars by decomposing them and dropping
+    /// combining marks. Indices and byte ranges of a match still refer to the chars of the
+    /// original target string. If the accents of a matched char also match,
+    /// [`Scoring::bonus_match_accent`] is added to the score.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::{format_simple, FuzzySearch};
+    ///
+    /// let m = FuzzySearch::new("cafe", "Café.md")
+    ///     .accent_insensitive()
+    ///     .best_match()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(format_simple(&m, "Café.md", "<", ">"), "<Café>.md");
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    pub fn accent_insensitive(mut self) -> Self {
+        self.options.fold_accents = true;
+
+        self
+    }
+
+    /// Treat targets as file paths, separated into segments by `/` or `\\`.
+    ///
+    /// Matches in the last segment (the file name) and right after a separator get additional
+    /// bonuses, and so do matches ending in the last segment. See [`Scoring::bonus_segment_start`],
+    /// [`Scoring::bonus_basename`] and [`Scoring::bonus_basename_end`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::FuzzySearch;
+    ///
+    /// let files = vec!["lib/src/main.rs", "src/lib.rs"];
+    ///
+    /// let ranked = FuzzySearch::with_query("lib").path_aware().rank(&files);
+    ///
+    /// assert_eq!(*ranked[0].candidate(), &"src/lib.rs");
+    /// ```
+    pub fn path_aware(mut self) -> Self {
+        self.options.paths = true;
+
+        self
+    }
+
+    /// Use custom rules for finding word starts in target strings.
+    ///
+    /// If not specified will use `WordBoundary::default()`.
+    pub fn word_boundary(mut self, boundary: WordBoundary) -> Self {
+        self.options.word_boundary = boundary;
+
+        self
+    }
+
+    /// When ranking, rank candidates with equal scores by the length of the target string and
+    /// then by the position of the first matched char, instead of by their input order.
+    /// Shorter targets and earlier matches rank higher.
+    ///
+    /// Candidates that are equal in all of these keep their input order. Shorthand for
+    /// `rank_by(Ranking::by_score_then_length())`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::FuzzySearch;
+    ///
+    /// let files = vec!["src/very/long/path/main.rs", "main.rs"];
+    ///
+    /// let ranked = FuzzySearch::with_query("main").rank(&files);
+    /// assert_eq!(ranked[0].score(), ranked[1].score());
+    /// assert_eq!(*ranked[0].candidate(), &"src/very/long/path/main.rs");
+    ///
+    /// let ranked = FuzzySearch::with_query("main")
+    ///     .break_ties_by_length()
+    ///     .rank(&files);
+    /// assert_eq!(*ranked[0].candidate(), &"main.rs");
+    /// ```
+    pub fn break_ties_by_length(self) -> Self {
+        self.rank_by(Ranking::by_score_then_length())
+    }
+
+    /// Use a custom order for ranked candidates, see [`Ranking`].
+    ///
+    /// If not specified will use `Ranking::default()`.
+    pub fn rank_by(mut self, ranking: Ranking) -> Self {
+        self.ranking = ranking;
 
         self
     }
 
+    /// Only returns matches with a score of at least `min_score`.
+    ///
+    /// Candidates that can't reach `min_score` are skipped early while matching, which makes
+    /// searching long targets faster. The match is the same as without a minimum score, it is
+    /// only left out if its score is too low. Skipping assumes the score of a [`Scorer`] never
+    /// gets better with longer gaps than with consecutive chars, which holds for
+    /// [`Scoring`](crate::Scoring) with non-negative bonuses.
+    ///
+    /// See [`Match::quality`] for a threshold that works across queries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::FuzzySearch;
+    ///
+    /// let ranked = FuzzySearch::with_query("sc")
+    ///     .min_score(100)
+    ///     .rank(&["SoccerCartoon", "discount"]);
+    ///
+    /// assert_eq!(ranked.len(), 1);
+    /// ```
+    pub fn min_score(mut self, min_score: isize) -> Self {
+        self.min_score = Some(min_score);
+
+        self
+    }
+
+    /// Only match query chars with at most `max_gap` unmatched chars between them (or grapheme
+    /// clusters, see [`FuzzySearch::grapheme_clusters`]).
+    ///
+    /// Targets where the query chars can't be matched close enough to each other don't match.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::FuzzySearch;
+    ///
+    /// assert!(FuzzySearch::new("abc", "a_b__c").max_gap(2).best_match().is_some());
+    /// assert!(FuzzySearch::new("abc", "a_b___c").max_gap(2).best_match().is_none());
+    /// ```
+    pub fn max_gap(mut self, max_gap: usize) -> Self {
+        self.max_gap = Some(max_gap);
+
+        self
+    }
+
+    /// Allow up to `max_typos` query chars to be missing from the target string or to be
+    /// swapped with the next query char, like `contorller` for `controller`.
+    ///
+    /// Every typo subtracts [`Scoring::penalty_typo`](crate::Scoring::penalty_typo) from the
+    /// score, see [`Match::typos`] for the typos of a match. Sub-matches are not skipped early
+    /// with a [`FuzzySearch::min_score`] if typos are allowed.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::{FuzzySearch, Typo};
+    ///
+    /// let m = FuzzySearch::new("contorller", "SoccerCartoonController")
+    ///     .max_typos(1)
+    ///     .best_match()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(m.typos().collect::<Vec<&Typo>>(), vec![&Typo::Swapped(4)]);
+    /// assert!(FuzzySearch::new("contorller", "SoccerCartoonController").best_match().is_none());
+    /// ```
+    pub fn max_typos(mut self, max_typos: usize) -> Self {
+        self.max_typos = max_typos;
+
+        self
+    }
+
+    /// Prepares `target` with the configuration of this search, see [`PreparedTarget`].
+    pub fn prepare(&self, target: &str) -> PreparedTarget {
+        PreparedTarget::with_options(target, &self.options)
+    }
+
     /// Finds the best match of the query in the target string.
     ///
     /// Always tries to match the _full_ pattern. A partial match is considered
     /// invalid and will return [`None`]. Will also return [`None`] in case the query or
     /// target string are empty.
+    ///
+    /// Of occurrences of a query char with equally scored matches of the rest of the query,
+    /// the last one is matched. Use [`Match::structural_eq`] to compare the alignments of
+    /// matches.
     pub fn best_match(self) -> Option<Match> {
-        let processed_query = process_query(self.query);
+        let target = self.target;
 
-        if processed_query.len() == 0 || self.target.len() == 0 {
-            return None;
+        self.compile().best_match(target)
+    }
+
+    /// Finds the best match of the query in the target string and explains how its score
+    /// was computed, see [`Explanation`].
+    ///
+    /// Returns [`None`] if there is no match, like [`FuzzySearch::best_match`].
+    pub fn explain(self) -> Option<Explanation> {
+        let target = self.target;
+
+        self.compile().explain(target)
+    }
+
+    /// Finds the best match of the query in a target that was prepared up front.
+    ///
+    /// Same as [`FuzzySearch::best_match`], but matches `target` instead of the target
+    /// string of this search (if any). Targets prepared with a different configuration than
+    /// this search are processed again, see [`FuzzySearch::prepare`].
+    pub fn best_match_prepared(self, target: &PreparedTarget) -> Option<Match> {
+        self.compile().best_match_prepared(target)
+    }
+
+    /// Parallel version of [`FuzzySearch::rank`], see [`Pattern::par_rank`].
+    #[cfg(feature = "rayon")]
+    pub fn par_rank<I>(self, candidates: I) -> Vec<Ranked<I::Item>>
+    where
+        S: Sync,
+        I: IntoParallelIterator,
+        I::Iter: IndexedParallelIterator,
+        I::Item: AsRef<str> + Send,
+    {
+        self.compile().par_rank(candidates)
+    }
+
+    /// Parallel version of [`FuzzySearch::rank_top`], see [`Pattern::par_rank_top`].
+    #[cfg(feature = "rayon")]
+    pub fn par_rank_top<I>(self, candidates: I, limit: usize) -> Vec<Ranked<I::Item>>
+    where
+        S: Sync,
+        I: IntoParallelIterator,
+        I::Iter: IndexedParallelIterator,
+        I::Item: AsRef<str> + Send,
+    {
+        self.compile().par_rank_top(candidates, limit)
+    }
+
+    /// Compiles the query and configuration of this search into a [`Pattern`] that can be
+    /// matched against any number of targets. The target string of this search (if any) is not used.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::FuzzySearch;
+    ///
+    /// let mut pattern = FuzzySearch::with_query("scc").case_sensitive().compile();
+    ///
+    /// assert!(pattern.best_match("SoccerCartoonController").is_none());
+    /// assert!(pattern.best_match("soccer cartoon controller").is_some());
+    /// ```
+    pub fn compile(self) -> Pattern<'a, S> {
+        self.compile_query(self.query)
+    }
+
+    /// Compiles `query` with the configuration of this search.
+    pub(crate) fn compile_query(&self, query: &str) -> Pattern<'a, S> {
+        let mut pattern = Pattern::new(query, self.scorer, self.case, self.options.clone());
+
+        pattern.set_ranking(self.ranking);
+        pattern.set_min_score(self.min_score);
+        pattern.set_max_gap(self.max_gap);
+        pattern.set_max_typos(self.max_typos);
+
+        pattern
+    }
+
+    /// Matches the query against every candidate and returns the matching ones, best match first.
+    ///
+    /// Candidates are ordered by the [`Ranking`] of this search (see [`FuzzySearch::rank_by`]),
+    /// by default candidates with equal scores keep their input order. The target string of
+    /// this search (if any) is not used.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// use sublime_fuzzy::FuzzySearch;
+    ///
+    /// let ranked = FuzzySearch::with_query("scc")
+    ///     .rank(vec!["SoccerCartoonController", "Scope", "SccsCoolController"]);
+    ///
+    /// let candidates = ranked.iter().map(|r| *r.candidate()).collect::<Vec<&str>>();
+    ///
+    /// assert_eq!(candidates, vec!["SccsCoolController", "SoccerCartoonController"]);
+    /// ```
+    pub fn rank<I>(self, candidates: I) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.compile().rank(candidates)
+    }
+
+    /// Like [`FuzzySearch::rank`], but only returns the best `limit` matches.
+    ///
+    /// Keeps at most `limit` matches around while ranking instead of sorting
+    /// all matching candidates.
+    pub fn rank_top<I>(self, candidates: I, limit: usize) -> Vec<Ranked<I::Item>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.compile().rank_top(candidates, limit)
+    }
+}
+
+// Not derived, which would require the scorer to be `Clone` instead of only its reference.
+impl<'a, S: ?Sized> Clone for FuzzySearch<'a, S> {
+    fn clone(&self) -> Self {
+        FuzzySearch {
+            query: self.query,
+            target: self.target,
+            scorer: self.scorer,
+            case: self.case,
+            options: self.options.clone(),
+            ranking: self.ranking,
+            min_score: self.min_score,
+            max_gap: self.max_gap,
+            max_typos: self.max_typos,
         }
+    }
+}
 
-        let occurrences = build_occurrences(&processed_query, self.target, self.case_insensitive);
+/// How the case of query chars is matched, see [`FuzzySearch::smart_case`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CaseMatching {
+    Sensitive,
+    Insensitive,
+    /// `Insensitive` for all lowercase queries, otherwise `Sensitive`
+    Smart,
+    /// Only uppercase query chars are matched case sensitively
+    SmartPerChar,
+}
 
-        let searcher = FuzzySearcher::new(
-            processed_query,
-            self.scoring.unwrap_or(&DEFAULT_SCORING),
-            self.case_insensitive,
-        );
+impl CaseMatching {
+    /// Decides on the case matching for `query`, never returns [`CaseMatching::Smart`].
+    pub(crate) fn resolve(self, query: &QueryChars) -> CaseMatching {
+        match self {
+            CaseMatching::Smart if query.iter().any(|qc| qc.original.is_uppercase()) => {
+                CaseMatching::Sensitive
+            }
+            CaseMatching::Smart => CaseMatching::Insensitive,
+            case => case,
+        }
+    }
 
-        searcher.best_match(&occurrences)
+    /// Returns `true` if chars are looked up by their lowercase form.
+    pub(crate) fn is_insensitive(self) -> bool {
+        self != CaseMatching::Sensitive
     }
 }
 
-struct FuzzySearcher<'a> {
+pub(crate) struct FuzzySearcher<'a, S: ?Sized> {
     query: QueryChars,
-    scoring: &'a Scoring,
-    match_cache: HashMap<(usize, usize, usize), Option<Match>>,
-    case_insensitive: bool,
+    scorer: &'a S,
+    /// Best sub-matches by matched query char, next query char, target unit, count of
+    /// consecutive chars and typos left, see [`FuzzySearcher::match_`]
+    match_cache: HashMap<(usize, usize, usize, usize, usize), Option<Match>>,
+    /// Resolved, see [`CaseMatching::resolve`]
+    case: CaseMatching,
+    accent_insensitive: bool,
+    min_score: Option<isize>,
+    max_gap: Option<usize>,
+    max_typos: usize,
+    /// Best possible scores of the query chars from every index on, see
+    /// [`FuzzySearcher::perfect_scores`]
+    bounds: Vec<isize>,
+    /// Best possible consecutive bonuses of the query chars
+    links: Vec<isize>,
 }
 
-impl<'a> FuzzySearcher<'a> {
-    fn new(query: QueryChars, scoring: &'a Scoring, case_insensitive: bool) -> Self {
+impl<'a, S: ?Sized> Clone for FuzzySearcher<'a, S> {
+    fn clone(&self) -> Self {
         FuzzySearcher {
+            query: self.query.clone(),
+            scorer: self.scorer,
+            match_cache: self.match_cache.clone(),
+            case: self.case,
+            accent_insensitive: self.accent_insensitive,
+            min_score: self.min_score,
+            max_gap: self.max_gap,
+            max_typos: self.max_typos,
+            bounds: self.bounds.clone(),
+            links: self.links.clone(),
+        }
+    }
+}
+
+impl<'a, S: Scorer + ?Sized> FuzzySearcher<'a, S> {
+    pub(crate) fn new(
+        query: QueryChars,
+        scorer: &'a S,
+        case: CaseMatching,
+        options: &ParseOptions,
+    ) -> Self {
+        let mut searcher = FuzzySearcher {
             match_cache: HashMap::with_capacity(query.len() * query.len()),
+            case,
             query,
-            scoring,
-            case_insensitive,
-        }
+            scorer,
+            accent_insensitive: options.fold_accents,
+            min_score: None,
+            max_gap: None,
+            max_typos: 0,
+            bounds: Vec::new(),
+            links: Vec::new(),
+        };
+
+        let (bounds, links) = searcher.perfect_scores(options.paths);
+
+        searcher.bounds = bounds;
+        searcher.links = links;
+
+        searcher
+    }
+
+    /// Sets the score matches have to reach, see [`FuzzySearch::min_score`].
+    pub(crate) fn set_min_score(&mut self, min_score: Option<isize>) {
+        self.min_score = min_score;
+    }
+
+    pub(crate) fn min_score(&self) -> Option<isize> {
+        self.min_score
+    }
+
+    /// Sets the maximum count of chars between matched chars, see [`FuzzySearch::max_gap`].
+    pub(crate) fn set_max_gap(&mut self, max_gap: Option<usize>) {
+        self.max_gap = max_gap;
+    }
+
+    pub(crate) fn max_gap(&self) -> Option<usize> {
+        self.max_gap
+    }
+
+    /// Sets the count of query chars that can be skipped or swapped, see
+    /// [`FuzzySearch::max_typos`].
+    pub(crate) fn set_max_typos(&mut self, max_typos: usize) {
+        self.max_typos = max_typos;
+    }
+
+    pub(crate) fn max_typos(&self) -> usize {
+        self.max_typos
     }
 
     #[inline(always)]
-    fn queried_char(&self, qc: &QueryChar) -> char {
-        if self.case_insensitive {
+    pub(crate) fn queried_char(&self, qc: &QueryChar) -> char {
+        if self.case.is_insensitive() {
             qc.lower
         } else {
             qc.original
         }
     }
 
+    /// Returns `true` if `qc` only matches target chars of the same case.
     #[inline(always)]
-    fn case_bonus(&self, query_idx: usize, occurrence: &Occurrence) -> isize {
-        if self.case_insensitive {
-            self.query
-                .get(query_idx)
-                .map_or(0, |c| (c.original == occurrence.char) as isize)
-                * self.scoring.bonus_match_case
-        } else {
-            0
+    pub(crate) fn matches_case(&self, qc: &QueryChar) -> bool {
+        match self.case {
+            CaseMatching::Sensitive => true,
+            CaseMatching::SmartPerChar => qc.original.is_uppercase(),
+            _ => false,
         }
     }
 
-    fn best_match(mut self, occurrences: &Occurrences) -> Option<Match> {
-        let qc = self.query.get(0)?;
+    /// Describes the match of the query char at `query_idx` by `occurrence` for scoring.
+    #[inline(always)]
+    fn char_match(&self, query_idx: usize, occurrence: &Occurrence) -> CharMatch {
+        let qc = &self.query[query_idx];
 
-        occurrences
-            .get(&self.queried_char(qc))?
+        CharMatch {
+            query_idx,
+            target_idx: occurrence.target_idx,
+            query_len: self.query.len(),
+            word_start: occurrence.start_kind,
+            matches_case: if self.case.is_insensitive() {
+                Some(qc.original == occurrence.char)
+            } else {
+                None
+            },
+            matches_accent: if self.accent_insensitive {
+                Some(qc.accent == occurrence.accent)
+            } else {
+                None
+            },
+            is_segment_start: occurrence.is_segment_start,
+            in_basename: occurrence.in_basename,
+        }
+    }
+
+    pub(crate) fn query(&self) -> &QueryChars {
+        &self.query
+    }
+
+    /// Finds the best match in `occurrences`. Can be called repeatedly, scored
+    /// sub-trees of previous calls are discarded.
+    pub(crate) fn best_match(&mut self, occurrences: &Occurrences) -> Option<Match> {
+        self.match_cache.clear();
+
+        let min_score = self.min_score.unwrap_or(isize::MIN);
+
+        if self.query.is_empty() || self.budget(0) > self.best_score() {
+            return None;
+        }
+
+        self.continue_match(None, 0, self.max_typos, occurrences)
+            .filter(|m| m.score() >= min_score)
+    }
+
+    /// Returns the score a sub-match starting with the query char at `query_idx` has to reach
+    /// for the whole match to reach the minimum score, even with a perfect match of the
+    /// preceding query chars.
+    ///
+    /// Sub-matches are never skipped if typos are allowed.
+    #[inline(always)]
+    fn budget(&self, query_idx: usize) -> isize {
+        if self.max_typos > 0 {
+            return isize::MIN;
+        }
+
+        self.min_score.map_or(isize::MIN, |min_score| {
+            let perfect_prefix = self.bounds[0] - self.bounds[query_idx] + self.links[query_idx];
+
+            min_score.saturating_sub(perfect_prefix)
+        })
+    }
+
+    /// Replays the scoring of `m`, which was returned by [`FuzzySearcher::best_match`] for
+    /// the same `occurrences`, char by char.
+    pub(crate) fn explain(&self, m: &Match, occurrences: &Occurrences) -> Vec<CharScore> {
+        let matched = m.matched_indices().cloned().collect::<Vec<usize>>();
+        let query_indices = m.query_indices(self.query.len());
+
+        let mut chars = matched
+            .iter()
+            .zip(&query_indices)
+            .zip(self.run_scores(&matched))
+            .map(|((&target_idx, &query_idx), (consecutive, gap))| {
+                let qc = &self.query[query_idx];
+                let match_case = self.matches_case(qc);
+
+                let occurrence = occurrences[&self.queried_char(qc)]
+                    .iter()
+                    .find(|o| o.target_idx == target_idx && can_match(qc, match_case, o))
+                    .expect("Matched char without occurrence");
+
+                let char_match = self.char_match(query_idx, occurrence);
+                let mut breakdown = self.scorer.explain_char(&char_match);
+
+                breakdown.consecutive = consecutive;
+                breakdown.gap = gap;
+
+                CharScore {
+                    char_match,
+                    char_idx: 0,
+                    breakdown,
+                }
+            })
+            .collect::<Vec<CharScore>>();
+
+        if let Some(last) = chars.last_mut() {
+            last.breakdown.consecutive += self.scorer.score_final_run(Self::final_run(&matched));
+        }
+
+        for typo in m.typos() {
+            let i = match *typo {
+                Typo::Missing(idx) => query_indices
+                    .iter()
+                    .position(|&query_idx| query_idx > idx)
+                    .unwrap_or(chars.len() - 1),
+                Typo::Swapped(idx) => query_indices
+                    .iter()
+                    .position(|&query_idx| query_idx == idx + 1)
+                    .expect("Swapped char not matched"),
+            };
+
+            chars[i].breakdown.typo += self.scorer.score_typo();
+        }
+
+        chars
+    }
+
+    /// Returns the score of a perfect match of the query, where every char is a consecutive
+    /// word start matching case and accents (and starting a path segment in the file name for
+    /// path aware searches).
+    pub(crate) fn best_score(&self) -> isize {
+        self.bounds.first().cloned().unwrap_or(0)
+    }
+
+    /// Returns the scores of the query chars from every index on and the consecutive bonuses
+    /// of every query char in a perfect match, see [`FuzzySearcher::best_score`].
+    ///
+    /// Assuming the bonus for consecutive chars is never negative, never decreases with the
+    /// length of the run and gaps are never scored better than consecutive chars, these are
+    /// upper bounds for the scores of the chars in any match.
+    fn perfect_scores(&self, paths: bool) -> (Vec<isize>, Vec<isize>) {
+        let kinds = [
+            WordStart::String,
+            WordStart::PathSeparator,
+            WordStart::Joiner,
+            WordStart::Member,
+            WordStart::Separator,
+            WordStart::CaseChange,
+            WordStart::Other,
+        ];
+        let matched = (0..self.query.len()).collect::<Vec<usize>>();
+
+        let char_scores = matched.iter().map(|&idx| {
+            kinds
+                .iter()
+                .map(|&kind| {
+                    self.scorer.score_char(&CharMatch {
+                        query_idx: idx,
+                        target_idx: idx,
+                        query_len: self.query.len(),
+                        word_start: Some(kind),
+                        matches_case: Some(true).filter(|_| self.case.is_insensitive()),
+                        matches_accent: Some(true).filter(|_| self.accent_insensitive),
+                        is_segment_start: paths,
+                        in_basename: paths,
+                    })
+                })
+                .max()
+                .unwrap_or(0)
+        });
+
+        let links = self
+            .run_scores(&matched)
+            .into_iter()
+            .map(|(consecutive, gap)| consecutive + gap)
+            .collect::<Vec<isize>>();
+
+        let mut scores = char_scores
+            .zip(&links)
+            .map(|(score, link)| score + link)
+            .collect::<Vec<isize>>();
+
+        // The run ending the match is part of the score of the last char, not of its link
+        if let Some(last) = scores.last_mut() {
+            *last += self.scorer.score_final_run(self.query.len() - 1);
+        }
+
+        scores.push(0);
+
+        for i in (0..self.query.len()).rev() {
+            scores[i] += scores[i + 1];
+        }
+
+        (scores, links)
+    }
+
+    /// Returns the consecutive bonus and gap penalty of every char of a match of the chars at
+    /// `matched`, as scored by [`FuzzySearcher::match_`] and [`Match::extend_with`]. The
+    /// bonus of the run ending the match (see [`Scorer::score_final_run`]) is not included.
+    fn run_scores(&self, matched: &[usize]) -> Vec<(isize, isize)> {
+        let mut run = 0;
+
+        matched
             .iter()
-            .filter_map(|o| self.match_(1, o, 0, &occurrences))
-            .max()
+            .enumerate()
+            .map(|(i, &target_idx)| {
+                let distance = i
+                    .checked_sub(1)
+                    .map_or(0, |prev| target_idx - matched[prev]);
+
+                if distance == 1 {
+                    run += 1;
+
+                    (self.scorer.score_consecutive(run), 0)
+                } else {
+                    run = 0;
+
+                    (
+                        0,
+                        if distance > 1 {
+                            self.scorer.score_gap(distance - 1)
+                        } else {
+                            0
+                        },
+                    )
+                }
+            })
+            .collect()
     }
 
+    /// Returns the count of consecutive chars before the last char of a match of the chars at
+    /// `matched`.
+    fn final_run(matched: &[usize]) -> usize {
+        matched
+            .windows(2)
+            .rev()
+            .take_while(|pair| pair[1] == pair[0] + 1)
+            .count()
+    }
+
+    /// Finds the best match of the query chars from `next_idx` on, starting with the query char
+    /// at `query_idx` matched by `occurrence`. Up to `typos` of the remaining query chars can
+    /// be skipped or swapped.
+    ///
+    /// Sub-trees that can't reach their [`FuzzySearcher::budget`] are skipped.
     fn match_(
         &mut self,
         query_idx: usize,
+        next_idx: usize,
         occurrence: &Occurrence,
         consecutive: usize,
+        typos: usize,
         occurrences: &Occurrences,
     ) -> Option<Match> {
-        let this_key = (query_idx, occurrence.target_idx, consecutive);
+        let this_key = (
+            query_idx,
+            next_idx,
+            occurrence.target_idx,
+            consecutive,
+            typos,
+        );
 
         // Already scored sub-tree
         if let Some(cached) = self.match_cache.get(&this_key) {
             return cached.clone();
         }
 
-        let next_char = self.query.get(query_idx);
+        let remaining = self.query.len() - next_idx;
 
-        let score = consecutive as isize * self.scoring.bonus_consecutive
-            + occurrence.is_start as isize * self.scoring.bonus_word_start
-            + self.case_bonus(query_idx - 1, occurrence);
+        let mut score = self
+            .scorer
+            .score_char(&self.char_match(query_idx, occurrence));
+
+        // The consecutive bonus of this char is part of the perfect prefix of the budget
+        let link = if consecutive > 0 {
+            self.scorer.score_consecutive(consecutive)
+        } else {
+            0
+        };
+        let budget = self.budget(query_idx).saturating_add(link);
+
+        score += link;
+
+        if remaining == 0 {
+            score += self.scorer.score_final_run(consecutive);
+        }
+
+        // Even a perfect match of the remaining query chars can't reach the budget
+        if score + self.bounds[next_idx] < budget {
+            self.match_cache.insert(this_key, None);
+
+            return None;
+        }
 
         let mut this_match = Match::with_matched(score, consecutive, vec![occurrence.target_idx]);
 
         // Successfully matched all query chars
-        if next_char.is_none() {
+        if remaining == 0 {
             self.match_cache.insert(this_key, Some(this_match.clone()));
 
             return Some(this_match);
         }
 
-        let occs = occurrences.get(&self.queried_char(next_char.unwrap()));
+        // Skipping all remaining query chars might be better than matching them far away
+        let ended = if remaining <= typos {
+            let mut ended = this_match.clone();
 
-        // Reached end of target without matching all query chars
-        if occs.is_none() {
-            self.match_cache.insert(this_key, None);
+            ended.add_typos(
+                (next_idx..self.query.len()).map(Typo::Missing),
+                remaining as isize * self.scorer.score_typo()
+                    + self.scorer.score_final_run(consecutive),
+            );
 
-            return None;
-        }
+            Some(ended)
+        } else {
+            None
+        };
 
-        let best_match = occs
-            .unwrap()
-            .iter()
-            .filter(|&o| o.target_idx > occurrence.target_idx)
-            .filter_map(|o| {
-                let distance = o.target_idx - occurrence.target_idx;
+        let best_match = self
+            .continue_match(
+                Some((occurrence, &this_match)),
+                next_idx,
+                typos,
+                occurrences,
+            )
+            .map(|m| {
+                this_match.extend_with(&m, self.scorer);
 
-                let new_consecutive = if distance == 1 { consecutive + 1 } else { 0 };
+                this_match
+            });
 
-                self.match_(query_idx + 1, o, new_consecutive, occurrences)
-            })
-            .max()
-            .and_then(|m| {
-                this_match.extend_with(&m, &self.scoring);
+        let best_match = match (best_match, ended) {
+            (Some(m), Some(ended)) if ended.score() > m.score() => Some(ended),
+            (best_match, ended) => best_match.or(ended),
+        };
 
-                Some(this_match)
-            });
+        // Weak sub-matches are dropped even if they were not skipped early. This way every
+        // sub-match that is kept scores better than any dropped one of the same query char,
+        // so dropping never changes which occurrence is matched.
+        let best_match = best_match.filter(|m| m.score() >= budget);
 
         self.match_cache.insert(this_key, best_match.clone());
 
         best_match
     }
+
+    /// Finds the best match of the query chars from `next_idx` on after `previous` (the
+    /// occurrence and match of the query char matched last), if any.
+    ///
+    /// Up to `typos` query chars can be skipped or swapped, the match is penalized for them.
+    /// Continuations are compared by their score after `previous`, including the consecutive
+    /// bonus or gap penalty between them.
+    fn continue_match(
+        &mut self,
+        previous: Option<(&Occurrence, &Match)>,
+        next_idx: usize,
+        typos: usize,
+        occurrences: &Occurrences,
+    ) -> Option<Match> {
+        let mut best_match: Option<(isize, Match)> = None;
+
+        let mut keep_better = |score: isize, m: Match| {
+            if best_match.as_ref().is_none_or(|(best, _)| score > *best) {
+                best_match = Some((score, m));
+            }
+        };
+
+        let remaining = self.query.len() - next_idx;
+        let previous_match = previous.map(|(_, m)| m);
+        let previous_consecutive = previous.map(|(_, m)| m.consecutive());
+
+        // The continuation without typos is always a candidate, so allowing typos never makes
+        // a match worse
+        if typos > 0 {
+            if let Some(m) = self.continue_match(previous, next_idx, 0, occurrences) {
+                keep_better(self.continued_score(previous_match, &m), m);
+            }
+        }
+
+        // Skip `skipped` query chars, the last query char matched has to be kept. Ties between
+        // occurrences are broken in favor of the last one.
+        for skipped in 0..=typos.min(remaining - 1) {
+            let idx = next_idx + skipped;
+            let typos = typos - skipped;
+
+            let mut best_occurrence: Option<(isize, Match)> = None;
+
+            for (o, distance) in self.next_occurrences(idx, previous.map(|(o, _)| o), occurrences) {
+                let consecutive = match (previous_consecutive, distance) {
+                    (Some(consecutive), 1) => consecutive + 1,
+                    _ => 0,
+                };
+
+                if let Some(m) = self.match_(idx, idx + 1, o, consecutive, typos, occurrences) {
+                    let score = self.continued_score(previous_match, &m);
+
+                    if best_occurrence
+                        .as_ref()
+                        .is_none_or(|(best, _)| score >= *best)
+                    {
+                        best_occurrence = Some((score, m));
+                    }
+                }
+            }
+
+            if let Some((mut score, mut m)) = best_occurrence {
+                if skipped > 0 {
+                    let penalty = skipped as isize * self.scorer.score_typo();
+
+                    m.add_typos((next_idx..idx).map(Typo::Missing), penalty);
+                    score += penalty;
+                }
+
+                keep_better(score, m);
+            }
+        }
+
+        // Swap the next two query chars
+        if typos > 0 && remaining > 1 && self.query[next_idx] != self.query[next_idx + 1] {
+            let swapped = self
+                .next_occurrences(next_idx + 1, previous.map(|(o, _)| o), occurrences)
+                .flat_map(|(first, distance)| {
+                    let consecutive = match (previous_consecutive, distance) {
+                        (Some(consecutive), 1) => consecutive + 1,
+                        _ => 0,
+                    };
+
+                    self.next_occurrences(next_idx, Some(first), occurrences)
+                        .map(move |(second, distance)| (first, consecutive, second, distance))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            let m = swapped
+                .into_iter()
+                .filter_map(|(first, consecutive, second, distance)| {
+                    let mut score = self
+                        .scorer
+                        .score_char(&self.char_match(next_idx + 1, first));
+
+                    if consecutive > 0 {
+                        score += self.scorer.score_consecutive(consecutive);
+                    }
+
+                    let second_consecutive = if distance == 1 { consecutive + 1 } else { 0 };
+
+                    self.match_(
+                        next_idx,
+                        next_idx + 2,
+                        second,
+                        second_consecutive,
+                        typos - 1,
+                        occurrences,
+                    )
+                    .map(|m| {
+                        let mut first_match =
+                            Match::with_matched(score, consecutive, vec![first.target_idx]);
+
+                        first_match.extend_with(&m, self.scorer);
+                        first_match
+                            .add_typos(Some(Typo::Swapped(next_idx)), self.scorer.score_typo());
+
+                        first_match
+                    })
+                })
+                .collect::<Vec<Match>>()
+                .into_iter()
+                .map(|m| (self.continued_score(previous_match, &m), m))
+                .max_by_key(|(score, _)| *score);
+
+            if let Some((score, m)) = m {
+                keep_better(score, m);
+            }
+        }
+
+        best_match.map(|(_, m)| m)
+    }
+
+    /// Returns the score of `m` continuing `previous` (if any), see [`Match::extend_with`].
+    #[inline(always)]
+    fn continued_score(&self, previous: Option<&Match>, m: &Match) -> isize {
+        previous.map_or(m.score(), |previous| {
+            previous.extended_score(m, self.scorer)
+        })
+    }
+
+    /// Returns the occurrences matching the query char at `query_idx` after `previous` (if any)
+    /// and their distances to it.
+    fn next_occurrences<'o>(
+        &self,
+        query_idx: usize,
+        previous: Option<&Occurrence>,
+        occurrences: &'o Occurrences,
+    ) -> impl Iterator<Item = (&'o Occurrence, usize)> + 'o {
+        let qc = &self.query[query_idx];
+        let match_case = self.matches_case(qc);
+        let max_gap = self.max_gap;
+        let previous = previous.map(|o| o.target_idx);
+        let qc = qc.clone();
+
+        occurrences
+            .get(&self.queried_char(&qc))
+            .into_iter()
+            .flatten()
+            .filter(move |o| {
+                previous.is_none_or(|previous| o.target_idx > previous)
+                    && can_match(&qc, match_case, o)
+            })
+            .map(move |o| (o, previous.map_or(0, |previous| o.target_idx - previous)))
+            // Occurrences are ordered by their position in the target string
+            .take_while(move |(_, distance)| {
+                previous.is_none() || max_gap.is_none_or(|max_gap| *distance <= max_gap + 1)
+            })
+    }
+}
+
+/// Returns `true` if `qc` can be matched by `occurrence`, which was looked up by
+/// [`FuzzySearcher::queried_char`]. If `match_case` is set the case has to match as well.
+#[inline(always)]
+fn can_match(qc: &QueryChar, match_case: bool, occurrence: &Occurrence) -> bool {
+    occurrence.tail == qc.tail && (!match_case || occurrence.char == qc.original)
 }
@@ -0,0 +1,73 @@
+use crate::parsing::{process_target, ParseOptions, TargetChars};
+
+/// A target string that was processed once so it can be matched against any
+/// number of queries without lowercasing it or looking for word starts again.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use sublime_fuzzy::{FuzzySearch, PreparedTarget};
+///
+/// let target = PreparedTarget::new("SoccerCartoonController");
+///
+/// for query in &["s", "sc", "scc"] {
+///     assert!(FuzzySearch::with_query(query).best_match_prepared(&target).is_some());
+/// }
+/// ```
+///
+/// Targets are prepared for a configuration, [`PreparedTarget::new`] uses the default one.
+/// Searches that change how target strings are processed (like matching grapheme clusters,
+/// ignoring accents or treating targets as paths) have to process targets prepared for a
+/// different configuration again. Use [`FuzzySearch::prepare`](crate::FuzzySearch::prepare)
+/// or [`Pattern::prepare`](crate::Pattern::prepare) to prepare targets for such searches.
+#[derive(Clone, Debug)]
+pub struct PreparedTarget {
+    string: String,
+    chars: TargetChars,
+    /// See [`ParseOptions::id`]
+    options_id: u64,
+}
+
+impl PreparedTarget {
+    /// Prepares `target` for matching with the default configuration.
+    pub fn new(target: &str) -> Self {
+        PreparedTarget::with_options(target, &ParseOptions::default())
+    }
+
+    pub(crate) fn with_options(target: &str, options: &ParseOptions) -> Self {
+        let mut chars = TargetChars::with_capacity(target.len());
+
+        process_target(target, options, &mut chars);
+
+        PreparedTarget {
+            string: target.to_owned(),
+            chars,
+            options_id: options.id(),
+        }
+    }
+
+    /// Returns the count of units (usually chars) in the target string.
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// Returns `true` if the target string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    /// Returns the target string.
+    pub fn as_str(&self) -> &str {
+        &self.string
+    }
+
+    pub(crate) fn chars(&self) -> &TargetChars {
+        &self.chars
+    }
+
+    pub(crate) fn options_id(&self) -> u64 {
+        self.options_id
+    }
+}